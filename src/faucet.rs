@@ -0,0 +1,317 @@
+//! A development faucet, enabled with the `faucet` feature.
+//!
+//! Accounts call `drip` to receive funds they can spend on anchoring. Each account may drip once
+//! per `Cooldown` blocks and at most `MaxDripsPerBlock` drips are paid out per block. The fee of a
+//! drip is refunded once it is paid out, failed drips are charged so they can not fill blocks for
+//! free.
+//!
+//! The faucet pays out in the DOCK token whenever `DockDrip` is non-zero, falling back to the
+//! native currency otherwise. Runtimes with a single currency use it for both `Currency` and
+//! `DockCurrency` and leave `DockDrip` at zero.
+
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::DispatchResultWithPostInfo,
+    ensure,
+    traits::{Currency, Get, Imbalance},
+    weights::{DispatchClass, Pays},
+    StorageMap, StorageValue,
+};
+use sp_runtime::traits::{Saturating, Zero};
+use system::ensure_signed;
+
+pub type BalanceOf<T> =
+    <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type DockBalanceOf<T> =
+    <<T as Trait>::DockCurrency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The native currency of the chain.
+    type Currency: Currency<Self::AccountId>;
+
+    /// The DOCK token.
+    type DockCurrency: Currency<Self::AccountId>;
+
+    /// Amount of DOCK paid out by a drip. When zero, `NativeDrip` is paid out instead.
+    type DockDrip: Get<DockBalanceOf<Self>>;
+
+    /// Amount of the native currency paid out by a drip when `DockDrip` is zero.
+    type NativeDrip: Get<BalanceOf<Self>>;
+
+    /// Number of blocks an account must wait between two drips.
+    type Cooldown: Get<Self::BlockNumber>;
+
+    /// Number of drips paid out per block, by all accounts together.
+    type MaxDripsPerBlock: Get<u32>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Faucet {
+        /// The block of the last drip paid out to an account.
+        LastDrip: map hasher(blake2_128_concat) T::AccountId => Option<T::BlockNumber>;
+
+        /// Number of drips paid out in the given block. Only the latest block is kept.
+        BlockDrips: (T::BlockNumber, u32);
+    }
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The account received a drip less than `Cooldown` blocks ago.
+        CoolingDown,
+        /// `MaxDripsPerBlock` drips were already paid out in this block.
+        BlockCapReached,
+        /// Nothing was paid out, the drip is below the existential deposit of the currency.
+        DripTooSmall,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// Pay out a drip to the caller, in DOCK if the faucet drips DOCK. The fee is refunded
+        /// if the drip is paid out.
+        #[weight = (10_000, DispatchClass::Normal, Pays::Yes)]
+        fn drip(origin) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let now = <system::Module<T>>::block_number();
+            if let Some(last) = LastDrip::<T>::get(&who) {
+                ensure!(
+                    last.saturating_add(T::Cooldown::get()) <= now,
+                    Error::<T>::CoolingDown
+                );
+            }
+            let (block, drips) = BlockDrips::<T>::get();
+            let drips = if block == now { drips } else { 0 };
+            ensure!(drips < T::MaxDripsPerBlock::get(), Error::<T>::BlockCapReached);
+
+            let dock = T::DockDrip::get();
+            if dock.is_zero() {
+                let paid = T::Currency::deposit_creating(&who, T::NativeDrip::get()).peek();
+                ensure!(!paid.is_zero(), Error::<T>::DripTooSmall);
+                Self::deposit_event(RawEvent::Dripped(who.clone(), paid));
+            } else {
+                let paid = T::DockCurrency::deposit_creating(&who, dock).peek();
+                ensure!(!paid.is_zero(), Error::<T>::DripTooSmall);
+                Self::deposit_event(RawEvent::DockDripped(who.clone(), paid));
+            }
+            LastDrip::<T>::insert(&who, now);
+            BlockDrips::<T>::put((now, drips + 1));
+            Ok(Pays::No.into())
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+        Balance = BalanceOf<T>,
+        DockBalance = DockBalanceOf<T>,
+    {
+        /// An account received a drip of the native currency.
+        Dripped(AccountId, Balance),
+        /// An account received a drip of DOCK.
+        DockDripped(AccountId, DockBalance),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::{
+        impl_outer_origin, parameter_types, traits::StorageMapShim, weights::Weight,
+    };
+    use sp_core::H256;
+    use sp_runtime::{
+        testing::Header,
+        traits::{BlakeTwo256, IdentityLookup},
+        Perbill,
+    };
+    use std::cell::Cell;
+
+    type Faucet = Module<Test>;
+    type Native = pallet_balances::Module<Test, pallet_balances::DefaultInstance>;
+    type Dock = pallet_balances::Module<Test, pallet_balances::Instance1>;
+
+    impl_outer_origin! {
+        pub enum Origin for Test where system = system {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    parameter_types! {
+        pub const BlockHashCount: u64 = 250;
+        pub const MaximumBlockWeight: Weight = 1024;
+        pub const MaximumBlockLength: u32 = 2 * 1024;
+        pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+        pub const ExistentialDeposit: u64 = 1;
+        pub const DockExistentialDeposit: u64 = 10;
+        pub const NativeDrip: u64 = 100;
+        pub const Cooldown: u64 = 5;
+        pub const MaxDripsPerBlock: u32 = 2;
+    }
+    impl system::Trait for Test {
+        type BaseCallFilter = ();
+        type Origin = Origin;
+        type Call = ();
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type BlockHashCount = BlockHashCount;
+        type MaximumBlockWeight = MaximumBlockWeight;
+        type DbWeight = ();
+        type BlockExecutionWeight = ();
+        type ExtrinsicBaseWeight = ();
+        type MaximumExtrinsicWeight = MaximumBlockWeight;
+        type MaximumBlockLength = MaximumBlockLength;
+        type AvailableBlockRatio = AvailableBlockRatio;
+        type Version = ();
+        type PalletInfo = ();
+        type AccountData = pallet_balances::AccountData<u64>;
+        type OnNewAccount = ();
+        type OnKilledAccount = ();
+        type SystemWeightInfo = ();
+    }
+    // The native currency keeps its balances in the system pallet, DOCK in its own storage.
+    impl pallet_balances::Trait for Test {
+        type Balance = u64;
+        type DustRemoval = ();
+        type Event = ();
+        type ExistentialDeposit = ExistentialDeposit;
+        type AccountStore = system::Module<Test>;
+        type WeightInfo = ();
+        type MaxLocks = ();
+    }
+    impl pallet_balances::Trait<pallet_balances::Instance1> for Test {
+        type Balance = u64;
+        type DustRemoval = ();
+        type Event = ();
+        type ExistentialDeposit = DockExistentialDeposit;
+        type AccountStore = StorageMapShim<
+            pallet_balances::Account<Test, pallet_balances::Instance1>,
+            system::CallOnCreatedAccount<Test>,
+            system::CallKillAccount<Test>,
+            u64,
+            pallet_balances::AccountData<u64>,
+        >;
+        type WeightInfo = ();
+        type MaxLocks = ();
+    }
+
+    thread_local! {
+        static DOCK_DRIP: Cell<u64> = Cell::new(0);
+    }
+
+    /// Amount of DOCK paid out by a drip, zero unless set with `set_dock_drip`.
+    pub struct DockDrip;
+    impl Get<u64> for DockDrip {
+        fn get() -> u64 {
+            DOCK_DRIP.with(|d| d.get())
+        }
+    }
+
+    fn set_dock_drip(amount: u64) {
+        DOCK_DRIP.with(|d| d.set(amount));
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type Currency = Native;
+        type DockCurrency = Dock;
+        type DockDrip = DockDrip;
+        type NativeDrip = NativeDrip;
+        type Cooldown = Cooldown;
+        type MaxDripsPerBlock = MaxDripsPerBlock;
+    }
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        system::GenesisConfig::default()
+            .build_storage::<Test>()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn drip() {
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            assert_eq!(Faucet::drip(Origin::signed(0)), Ok(Pays::No.into()));
+            assert_eq!(Native::free_balance(0), 100);
+            assert_eq!(Dock::free_balance(0), 0);
+        });
+    }
+
+    #[test]
+    fn drip_prefers_dock() {
+        new_test_ext().execute_with(|| {
+            set_dock_drip(17);
+            system::Module::<Test>::set_block_number(1);
+            Faucet::drip(Origin::signed(0)).unwrap();
+            assert_eq!(Dock::free_balance(0), 17);
+            assert_eq!(Native::free_balance(0), 0);
+        });
+    }
+
+    #[test]
+    fn drip_below_existential_deposit() {
+        new_test_ext().execute_with(|| {
+            set_dock_drip(7);
+            system::Module::<Test>::set_block_number(1);
+            let failed = Faucet::drip(Origin::signed(0)).unwrap_err();
+            assert_eq!(failed.error, Error::<Test>::DripTooSmall.into());
+            assert_eq!(failed.post_info.pays_fee, Pays::Yes);
+            // nothing was paid out, so the account may try again right away
+            assert_eq!(Dock::free_balance(0), 0);
+            assert_eq!(LastDrip::<Test>::get(0), None);
+            set_dock_drip(17);
+            Faucet::drip(Origin::signed(0)).unwrap();
+        });
+    }
+
+    #[test]
+    fn cooldown() {
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            Faucet::drip(Origin::signed(0)).unwrap();
+            system::Module::<Test>::set_block_number(5);
+            let failed = Faucet::drip(Origin::signed(0)).unwrap_err();
+            assert_eq!(failed.error, Error::<Test>::CoolingDown.into());
+            assert_eq!(failed.post_info.pays_fee, Pays::Yes);
+            system::Module::<Test>::set_block_number(6);
+            Faucet::drip(Origin::signed(0)).unwrap();
+            assert_eq!(Native::free_balance(0), 200);
+
+            // a cooldown reaching past the last block does not overflow
+            LastDrip::<Test>::insert(0, u64::max_value() - 1);
+            system::Module::<Test>::set_block_number(u64::max_value() - 1);
+            let failed = Faucet::drip(Origin::signed(0)).unwrap_err();
+            assert_eq!(failed.error, Error::<Test>::CoolingDown.into());
+        });
+    }
+
+    #[test]
+    fn block_cap() {
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            Faucet::drip(Origin::signed(0)).unwrap();
+            Faucet::drip(Origin::signed(1)).unwrap();
+            let failed = Faucet::drip(Origin::signed(2)).unwrap_err();
+            assert_eq!(failed.error, Error::<Test>::BlockCapReached.into());
+            assert_eq!(failed.post_info.pays_fee, Pays::Yes);
+            assert_eq!(Native::free_balance(2), 0);
+            system::Module::<Test>::set_block_number(2);
+            Faucet::drip(Origin::signed(2)).unwrap();
+        });
+    }
+}
@@ -3,6 +3,8 @@ use blake2::{digest::generic_array::GenericArray, Digest};
 use codec::{Decode, Encode};
 use core::{fmt::Debug, marker::PhantomData};
 use derivative::Derivative;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 
 pub trait Hash {
     fn hash<D: Digest>(&self, hasher: &mut D);
@@ -66,7 +68,25 @@ impl Hash for u8 {
     }
 }
 
+/// Big endian, like the account ids and timestamps hashed elsewhere.
+impl Hash for u64 {
+    fn hash<D: Digest>(&self, hasher: &mut D) {
+        hasher.input(self.to_be_bytes());
+    }
+}
+
 #[derive(Encode, Decode, Derivative)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "std",
+    serde(
+        transparent,
+        bound(
+            serialize = "Output: Serialize",
+            deserialize = "Output: Deserialize<'de>"
+        )
+    )
+)]
 #[derivative(
     Clone(bound = "Output: Clone"),
     PartialEq(bound = "Output: PartialEq"),
@@ -76,6 +96,7 @@ impl Hash for u8 {
 )]
 pub struct Hashed<Preimage: ?Sized, Output> {
     pub hash: Output,
+    #[cfg_attr(feature = "std", serde(skip))]
     _spook: PhantomData<*const Preimage>,
 }
 
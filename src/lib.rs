@@ -1,19 +1,38 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "faucet")]
+pub mod faucet;
 pub mod fromalt;
 pub mod hasher;
 pub mod merkle;
+pub mod migration;
+pub mod runtime_api;
+pub mod snapshot;
+pub mod sparse;
+#[cfg(feature = "std")]
+pub mod status_list;
 
 use crate::fromalt::FromAlt;
 use crate::hasher::{Hash, Hashed};
-use crate::merkle::{verify_proof, MerkleRoot, ProofElement};
+use crate::merkle::{
+    verify_compact_proof, verify_multiproof, verify_proof, CompactProof, MerkleRoot, MultiProof,
+    ProofElement,
+};
+use crate::snapshot::SnapshotOf;
+use crate::sparse::{EmptySubtrees, SparseMerkleRoot, SparseProof};
 use blake2::digest::{generic_array::GenericArray, Digest};
 use codec::{Decode, Encode};
 use core::fmt::Debug;
 use frame_support::{
-    decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure, Parameter, StorageMap,
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo},
+    ensure,
+    traits::{EnsureOrigin, Get},
+    weights::{Pays, Weight},
+    IterableStorageDoubleMap, Parameter, StorageDoubleMap, StorageMap, StorageValue,
 };
-use system::ensure_signed;
+use sp_runtime::traits::{One, Saturating};
+use system::{ensure_root, ensure_signed};
 
 /// Max proof size for revocations.
 /// This decides the number of accounts that can be authorized to revoke a root.
@@ -23,6 +42,17 @@ use system::ensure_signed;
 // attackable?
 pub const MAX_PROOF_SIZE: usize = 16;
 
+/// Max number of roots or leaves handled by a single batch call.
+/// Duplicate detection within a batch is quadratic so the batch size must stay bounded.
+pub const MAX_BATCH_SIZE: usize = 256;
+
+/// Weight charged once per call, regardless of the number of items processed.
+const BASE_WEIGHT: Weight = 10_000;
+/// Weight charged for each root or leaf in a batch call.
+const PER_ITEM_WEIGHT: Weight = 10_000;
+/// Weight charged for each hash computed by calls that hash more than a handful of times.
+const PER_HASH_WEIGHT: Weight = 1_000;
+
 /// The output of the hash function used constructing merkle roots configurable.
 /// By default it is the the output specified in system::Trait.
 pub trait Trait: system::Trait {
@@ -37,31 +67,218 @@ pub trait Trait: system::Trait {
         + Decode
         + Default
         + Hash
+        + AsRef<[u8]>
         + FromAlt<GenericArray<u8, <Self::TreeHash as Digest>::OutputSize>>;
 
+    /// Number of distinct members of an admin set that must vote to disable an admin leaf
+    /// before it is disabled. A value of 1 (or 0) lets any single member disable another.
+    type DisableThreshold: Get<u32>;
+
+    /// Number of blocks after `commit_anchor` during which the commitment may be revealed.
+    /// Commitments that are not revealed in time are removed.
+    type RevealWindow: Get<Self::BlockNumber>;
+
+    /// Number of roots a single account may anchor within `QuotaWindow` blocks.
+    type MaxAnchorsPerAccount: Get<u32>;
+
+    /// Number of roots that may be anchored in a single block, by all accounts together.
+    type MaxAnchorsPerBlock: Get<u32>;
+
+    /// Length, in blocks, of the sliding window `MaxAnchorsPerAccount` applies to.
+    type QuotaWindow: Get<Self::BlockNumber>;
+
+    /// Number of anchor commitments that may be made in a single block, by all accounts
+    /// together. Bounds the work of removing the commitments whose reveal window closes.
+    type MaxCommitmentsPerBlock: Get<u32>;
+
+    /// Number of storage keys of an earlier layout visited per block while moving them out of
+    /// the way after a runtime upgrade. Bounds the weight of the migration in each block, see
+    /// `migration::park_v1_entries`.
+    type MigrationKeysPerBlock: Get<u32>;
+
+    /// The origin allowed to anchor roots, for example any signed account, the members of a
+    /// whitelist or a collective. Commit-reveal anchoring additionally requires a signed origin
+    /// since the commitment is bound to the committing account.
+    type AnchorOrigin: EnsureOrigin<Self::Origin>;
+
+    /// The origin allowed to revoke, suspend and remove anchoring data regardless of admin sets,
+    /// usually root or a governance collective.
+    type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+    /// The current time according to the chain, usually taken from the timestamp pallet.
+    fn current_time() -> UnixTimeSeconds;
+
     /// hash Self::AccountId using Self::Treehash
     fn hash_account_id(account: &Self::AccountId, hasher: &mut Self::TreeHash);
+
+    /// hash an admin leaf that is scoped to `role` using Self::TreeHash
+    ///
+    /// The default appends the role to the output of `hash_account_id`. This keeps role scoped
+    /// leaves distinct from unscoped leaves as long as accounts are hashed as fixed size values.
+    fn hash_admin_role(account: &Self::AccountId, role: Role, hasher: &mut Self::TreeHash) {
+        Self::hash_account_id(account, hasher);
+        role.hash(hasher);
+    }
 }
 
 /// Some arbitrary hashable document.
 pub struct Document;
+/// A GZIP-compressed bitstring status list, see `status_list`.
+pub struct StatusList;
+/// A commitment to anchoring a root under an admin set, `H(admins || root || salt)`.
+pub struct AnchorCommitment;
 /// u64::max() is around 584_942_417_355 years in the future.
 pub type UnixTimeSeconds = u64;
 #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub enum Revokable<T> {
     NotRevoked(T),
     Revoked,
 }
 
+/// The status of an anchor at some point in time, as reported by `lookup_anchor`.
+/// Carries the block at which the root was anchored unless the anchor is revoked.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub enum AnchorStatus<BlockNumber> {
+    Valid(BlockNumber),
+    /// The validity period of the anchor has not started yet.
+    NotYetValid(BlockNumber),
+    /// The validity period of the anchor has ended.
+    Expired(BlockNumber),
+    Revoked,
+}
+
+/// The status of a document at some point in time, as reported by `verify_document`.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub enum DocumentStatus {
+    /// The document is part of a valid anchor and is not suspended.
+    Valid,
+    /// The root was never anchored under the admin set.
+    NotAnchored,
+    /// The proof does not show that the document is part of the root.
+    NotIncluded,
+    NotYetValid,
+    Expired,
+    Revoked,
+    Suspended,
+}
+
+/// Releases of this pallet that changed its storage layout, see `migration`.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Releases {
+    /// `Anchors` and `SuspendedLeaves` are maps keyed by tuples.
+    V1_0_0,
+    /// `Anchors` and `SuspendedLeaves` are double maps keyed by admin set first. Entries of the
+    /// earlier layout are kept in `LegacyAnchors` and `LegacySuspendedLeaves` until migrated.
+    V2_0_0,
+}
+
+impl Releases {
+    /// The storage layout of this version of the pallet.
+    pub const CURRENT: Releases = Releases::V2_0_0;
+}
+
+impl Default for Releases {
+    /// Storage written before versioning was introduced.
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
+/// The actions an admin leaf can be scoped to.
+///
+/// An admins root may contain unscoped leaves, `H(account)`, which are authorized to do anything,
+/// and role scoped leaves, `H(account || role)`, which are only authorized to act in that role.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    /// May revoke anchors.
+    Revoker,
+    /// May suspend leaves.
+    Suspender,
+    /// May lift suspensions before they end.
+    Reinstater,
+}
+
+impl Hash for Role {
+    fn hash<D: Digest>(&self, hasher: &mut D) {
+        (*self as u8).hash(hasher)
+    }
+}
+
+/// A period of time, bounds included. Limits when an admin leaf is authorized to act, or when an
+/// anchor is valid.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct Validity {
+    pub not_before: UnixTimeSeconds,
+    pub not_after: UnixTimeSeconds,
+}
+
+impl Validity {
+    pub fn contains(&self, time: UnixTimeSeconds) -> bool {
+        self.not_before <= time && time <= self.not_after
+    }
+}
+
+impl Hash for Validity {
+    fn hash<D: Digest>(&self, hasher: &mut D) {
+        (self.not_before, self.not_after).hash(hasher)
+    }
+}
+
+/// A proof of membership in an admin set.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct AdminProof<O> {
+    /// The validity period committed to by the proven leaf, if it has one.
+    ///
+    /// An admin leaf with a validity period is hashed as `H(account || validity)`, or as
+    /// `H(account || role || validity)` when it is also scoped to a role.
+    pub validity: Option<Validity>,
+    /// Path from the proven leaf to the admins root.
+    pub path: CompactProof<O>,
+}
+
+impl<O> AdminProof<O> {
+    /// Weight of checking this proof: at most two leaves, unscoped and scoped, are hashed up a
+    /// path of at most `MAX_PROOF_SIZE` hashes.
+    fn weight(&self) -> Weight {
+        PER_HASH_WEIGHT * 2 * (self.path.hashes.len().min(MAX_PROOF_SIZE) + 1) as Weight
+    }
+}
+
+impl<O> From<Vec<ProofElement<O>>> for AdminProof<O> {
+    /// A proof for a leaf without a validity period.
+    fn from(path: Vec<ProofElement<O>>) -> Self {
+        AdminProof {
+            validity: None,
+            path: path.into(),
+        }
+    }
+}
+
+/// Authority to act in a role on behalf of an admin set, granted to an account that is not a
+/// member of the set.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct Delegation<AdminLeaf> {
+    /// The delegation is active as long as `current_time() <= expires`.
+    pub expires: UnixTimeSeconds,
+    /// The admin leaf that granted the delegation. Disabling this leaf ends the delegation.
+    pub granted_by: AdminLeaf,
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as TemplateModule {
         /// Scoping Anchors to the parties with revocation permission prevents frontrunning
         /// attacks.
         /// When a party proves their membership in "Administrators", they may revoke this anchor.
-        Anchors: map (
-            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
-            MerkleRoot<Document, T::TreeHashOut>
-        ) => Option<Revokable<T::BlockNumber>>;
+        ///
+        /// Keyed by "Administrators" first so the anchors of an admin set can be enumerated.
+        Anchors: double_map
+            hasher(blake2_128_concat) MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            hasher(identity) MerkleRoot<Document, T::TreeHashOut>
+            => Option<Revokable<T::BlockNumber>>;
 
         /// Suspensions mapped to suspension expiration.
         /// Setting suspension expiration to u64::max() is a permanent revocation.
@@ -72,15 +289,256 @@ decl_storage! {
         /// If `current_time() == suspension_end`, then suspension is still active.
         /// For example, if `current_time() == u64::max() == suspension_end`, the leaf is still
         /// considered suspended.
-        SuspendedLeaves: map (
+        ///
+        /// Keyed by "Administrators" first so the suspensions of an admin set can be enumerated.
+        SuspendedLeaves: double_map
+            hasher(blake2_128_concat) MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            hasher(identity) Hashed<Document, T::TreeHashOut>
+            => Option<UnixTimeSeconds>;
+
+        /// Anchors that were moved to a new set of administrators, mapped to the new set and the
+        /// block at which the move happened.
+        /// Entries are only removed along with the anchor they lead to, by `force_remove`. Until
+        /// then the full rotation history of an anchor can be recovered by following the chain of
+        /// rotations.
+        AdminRotations: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Previous Administrators
+            MerkleRoot<Document, T::TreeHashOut>
+        ) => Option<(MerkleRoot<T::AccountId, T::TreeHashOut>, T::BlockNumber)>;
+
+        /// The reverse of `AdminRotations`: roots, by the admin set they were rotated to, mapped
+        /// to the admin set they were rotated from.
+        RotatedFrom: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            MerkleRoot<Document, T::TreeHashOut>
+        ) => Option<MerkleRoot<T::AccountId, T::TreeHashOut>>;
+
+        /// Admin leaves that may no longer act on behalf of an admin set, for example because
+        /// the key behind them was compromised. Proofs for a disabled leaf are refused.
+        DisabledAdmins: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            Hashed<T::AccountId, T::TreeHashOut>
+        ) => bool;
+
+        /// Members of an admin set who voted to disable an admin leaf that is not disabled yet.
+        DisableVotes: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            Hashed<T::AccountId, T::TreeHashOut>
+        ) => Vec<Hashed<T::AccountId, T::TreeHashOut>>;
+
+        /// Temporary authority granted to non-members by members of an admin set.
+        Delegations: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            T::AccountId, // Delegate
+            Role
+        ) => Option<Delegation<Hashed<T::AccountId, T::TreeHashOut>>>;
+
+        /// The admin sets each root has an entry under in `Anchors`, so the anchors of a root can
+        /// be found without knowing the admin sets. Roots are chosen by whoever anchors them, so
+        /// both keys are hashed.
+        AnchorsByRoot: double_map
+            hasher(blake2_128_concat) MerkleRoot<Document, T::TreeHashOut>,
+            hasher(blake2_128_concat) MerkleRoot<T::AccountId, T::TreeHashOut> // Administrators
+            => ();
+
+        /// Validity periods of anchors that were created with one. Anchors without an entry are
+        /// valid from creation until revoked.
+        AnchorValidity: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            MerkleRoot<Document, T::TreeHashOut>
+        ) => Option<Validity>;
+
+        /// Revoked anchors that were replaced by a new version, mapped to the root of the new
+        /// version. The new version is anchored under the same admins.
+        Supersessions: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            MerkleRoot<Document, T::TreeHashOut>
+        ) => Option<MerkleRoot<Document, T::TreeHashOut>>;
+
+        /// The reverse of `Supersessions`: new versions of a root, by the admin set they are
+        /// anchored under, mapped to the key of the supersession that points to them. Moved
+        /// along when the new version is rotated to other admins.
+        PreviousVersions: map hasher(blake2_128_concat) (
+            MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
+            MerkleRoot<Document, T::TreeHashOut>
+        ) => Option<(
             MerkleRoot<T::AccountId, T::TreeHashOut>, // Administrators
-            Hashed<Document, T::TreeHashOut>
-        ) => Option<UnixTimeSeconds>;
+            MerkleRoot<Document, T::TreeHashOut>
+        )>;
+
+        /// Anchor commitments that were not revealed yet, by the account that committed, mapped
+        /// to the last block at which the commitment may be revealed.
+        ///
+        /// Keyed by account first so a commitment copied from the transaction pool by another
+        /// account does not block the original one.
+        AnchorCommitments: double_map
+            hasher(blake2_128_concat) T::AccountId,
+            hasher(identity) Hashed<AnchorCommitment, T::TreeHashOut>
+            => Option<T::BlockNumber>;
+
+        /// Anchor commitments by the last block at which they may be revealed. Used to remove
+        /// unrevealed commitments once their reveal window closes. Holds at most
+        /// `MaxCommitmentsPerBlock` entries per block.
+        CommitmentDeadlines: map hasher(twox_64_concat) T::BlockNumber
+            => Vec<(T::AccountId, Hashed<AnchorCommitment, T::TreeHashOut>)>;
+
+        /// Number of roots anchored by an account in each of the blocks of the current
+        /// `QuotaWindow` it anchored in, oldest first.
+        RecentAnchors: map hasher(blake2_128_concat) T::AccountId => Vec<(T::BlockNumber, u32)>;
+
+        /// Accounts by the block at which their latest entry of `RecentAnchors` leaves the
+        /// `QuotaWindow`. Used to remove the entries of accounts that stopped anchoring. Holds at
+        /// most `MaxAnchorsPerBlock` accounts per block.
+        QuotaExpiries: map hasher(twox_64_concat) T::BlockNumber => Vec<T::AccountId>;
+
+        /// Number of roots anchored in the given block. Only the latest block is kept.
+        BlockAnchors: (T::BlockNumber, u32);
+
+        /// Root of the sparse merkle tree of leaves revoked by an admin set through
+        /// `update_revocation_root`. Admin sets without an entry have revoked nothing.
+        RevocationRoots: map hasher(blake2_128_concat) MerkleRoot<T::AccountId, T::TreeHashOut>
+            => Option<SparseMerkleRoot<Document, T::TreeHashOut>>;
+
+        /// Hash of the latest GZIP-compressed status list published by an admin set.
+        StatusLists: map hasher(blake2_128_concat) MerkleRoot<T::AccountId, T::TreeHashOut>
+            => Option<Hashed<StatusList, T::TreeHashOut>>;
+
+        /// Anchors written in the `Releases::V1_0_0` layout, keyed by the blake2_256 hash of the
+        /// (admins, root) key they were written under. See `migration`.
+        LegacyAnchors: map hasher(identity) [u8; 32] => Option<Revokable<T::BlockNumber>>;
+
+        /// Suspensions written in the `Releases::V1_0_0` layout, keyed by the blake2_256 hash of
+        /// the (admins, leaf) key they were written under. See `migration`.
+        LegacySuspendedLeaves: map hasher(identity) [u8; 32] => Option<UnixTimeSeconds>;
+
+        /// The raw storage key up to which entries of the `Releases::V1_0_0` layout were moved to
+        /// `LegacyAnchors` and `LegacySuspendedLeaves`. Present while the move is in progress.
+        ParkingCursor: Option<Vec<u8>>;
+
+        /// The storage layout currently in use. New chains start out with the current layout,
+        /// existing chains are upgraded by `on_runtime_upgrade`.
+        StorageVersion build(|_: &GenesisConfig<T>| Releases::CURRENT): Releases;
+    }
+    add_extra_genesis {
+        /// Roots anchored before the chain starts, with the block each is recorded as anchored at.
+        config(anchors): Vec<(
+            MerkleRoot<T::AccountId, T::TreeHashOut>,
+            MerkleRoot<Document, T::TreeHashOut>,
+            T::BlockNumber,
+        )>;
+        /// Leaves suspended before the chain starts, with the time each suspension ends.
+        config(suspensions): Vec<(
+            MerkleRoot<T::AccountId, T::TreeHashOut>,
+            Hashed<Document, T::TreeHashOut>,
+            UnixTimeSeconds,
+        )>;
+        /// A snapshot exported from another chain, imported after `anchors` and `suspensions`.
+        config(snapshot): Option<SnapshotOf<T>>;
+        build(|config: &GenesisConfig<T>| {
+            for (admins, root, block) in &config.anchors {
+                assert!(
+                    !Anchors::<T>::contains_key(admins, root),
+                    "root anchored twice in genesis"
+                );
+                Anchors::<T>::insert(admins, root, Revokable::NotRevoked(*block));
+                Module::<T>::index_anchor(admins, root);
+            }
+            for (admins, leaf, end) in &config.suspensions {
+                assert!(
+                    !SuspendedLeaves::<T>::contains_key(admins, leaf),
+                    "leaf suspended twice in genesis"
+                );
+                SuspendedLeaves::<T>::insert(admins, leaf, end);
+            }
+            if let Some(snapshot) = &config.snapshot {
+                snapshot::import::<T>(snapshot).expect("invalid snapshot in genesis");
+            }
+        });
+    }
+}
+
+/// Read the anchors and suspensions of a `GenesisConfig` from the JSON fragment a chain spec
+/// holds for this pallet, e.g. `{ "anchors": [[admins, root, 0]], "suspensions": [] }`.
+#[cfg(feature = "std")]
+impl<T: Trait> GenesisConfig<T> {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The account anchored `MaxAnchorsPerAccount` roots within the last `QuotaWindow` blocks.
+        AccountQuotaExceeded,
+        /// `MaxAnchorsPerBlock` roots were already anchored in this block.
+        BlockQuotaExceeded,
+        /// `MaxCommitmentsPerBlock` anchor commitments were already made in this block.
+        CommitmentQuotaExceeded,
+        /// The root was already anchored under the admin set.
+        AlreadyAnchored,
+        /// The validity period ends before it starts.
+        InvalidValidity,
+        /// The anchor is already revoked.
+        AlreadyRevoked,
+        /// The anchor was rotated to another admin set, act on it through that set.
+        AnchorRotated,
+        /// The root is not anchored under the admin set.
+        NoSuchAnchor,
+        /// The anchor was already superseded by a new version.
+        AlreadySuperseded,
+        /// The leaf is already suspended until the given time or later.
+        AlreadySuspended,
+        /// The leaf is not suspended.
+        NotSuspended,
+        /// The leaf is suspended until u64::max(), which can not be lifted.
+        PermanentlyRevoked,
+        /// The batch holds more than `MAX_BATCH_SIZE` items.
+        BatchTooLarge,
+        /// The batch holds no items.
+        EmptyBatch,
+        /// The same root or leaf appears more than once in the batch.
+        DuplicateInBatch,
+        /// The account already made this anchor commitment.
+        CommitmentExists,
+        /// The account made no such anchor commitment, or it was removed after its reveal window.
+        NoSuchCommitment,
+        /// The reveal window of the commitment has closed.
+        RevealWindowClosed,
+        /// An update does not match the current revocation registry root.
+        InvalidUpdateProof,
+        /// The proof does not show membership of the account in the admin set.
+        InvalidProof,
+        /// The proof holds more than `MAX_PROOF_SIZE` hashes.
+        ProofTooLarge,
+        /// The admin leaf is outside of its validity period.
+        AdminNotValid,
+        /// The admin leaf was disabled.
+        AdminDisabled,
+        /// The admin leaf was already disabled.
+        AdminAlreadyDisabled,
+        /// The member already voted to disable the admin leaf.
+        AlreadyVoted,
+        /// The delegation would outlive the validity period of the granting admin leaf.
+        DelegationTooLong,
+        /// The delegation was granted by another admin leaf.
+        DelegationNotOwned,
+        /// The delegation has expired.
+        DelegationExpired,
+        /// The snapshot was written in another format version.
+        UnsupportedSnapshotVersion,
+        /// The checksum of the snapshot does not match its entries.
+        SnapshotChecksumMismatch,
+        /// A rotation in the snapshot does not lead to an anchor or would form a cycle.
+        InvalidRotation,
+        /// Storage holds entries of an earlier layout that were not migrated yet.
+        UnmigratedEntries,
     }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
         fn deposit_event() = default;
 
         /// Anyone who can prove their membership in the set represented by `admins` is authorized
@@ -88,39 +546,61 @@ decl_module! {
         ///
         /// If `admins` represents the empty set, the anchor is irrevokable. In other words,
         /// if `admins` is a hash consisting of all zeros, the anchor is irrevokable.
+        ///
+        /// If `validity` is given, the anchor is only valid during that period, no revocation is
+        /// needed once it has passed.
+        #[weight = BASE_WEIGHT]
         fn create_anchor(
             origin,
             admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
-            root: MerkleRoot<Document, T::TreeHashOut>
+            root: MerkleRoot<Document, T::TreeHashOut>,
+            validity: Option<Validity>,
         ) -> DispatchResult {
-            ensure_signed(origin)?; // Is this needed?
-            let key = (admins, root);
-            ensure!(!Anchors::<T>::exists(&key), "The root has already been anchored.");
-            Anchors::<T>::insert(
-                &key,
-                Revokable::NotRevoked(<system::Module<T>>::block_number()),
-            );
+            let sender = ensure_signed(origin.clone()).ok();
+            T::AnchorOrigin::ensure_origin(origin)?;
+            ensure!(!Self::is_anchored(&admins, &root), Error::<T>::AlreadyAnchored);
+            if let Some(validity) = &validity {
+                ensure!(
+                    validity.not_before <= validity.not_after,
+                    Error::<T>::InvalidValidity
+                );
+            }
+            Self::use_quota(sender.as_ref(), 1)?;
+            if let Some(validity) = validity {
+                AnchorValidity::<T>::insert((&admins, &root), validity);
+            }
+            Self::insert_anchor(admins, root);
             Ok(())
         }
 
         /// An anchor can be revoked even before it is posted.
+        ///
+        /// Successful revocations of an existing anchor are free if `admins` owns anchors, see
+        /// `revocation_fee`. Revocations before the anchor is posted and failed attempts are
+        /// charged.
+        #[weight = BASE_WEIGHT + proof.weight()]
         fn revoke_anchor(
             origin,
             admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
             root: MerkleRoot<Document, T::TreeHashOut>,
-            proof: Vec<ProofElement<T::TreeHashOut>>,
-        ) -> DispatchResult {
+            proof: AdminProof<T::TreeHashOut>,
+        ) -> DispatchResultWithPostInfo {
             let sender = ensure_signed(origin)?;
-            let key = (admins.clone(), root);
-            ensure!(Anchors::<T>::get(&key) != Some(Revokable::Revoked), "anchor already revoked");
-            let valid = verify_proof::<T::TreeHash, T::AccountId, T::TreeHashOut>(
-                &admins,
-                &proof,
-                &hash_account_id::<T>(&sender),
-            );
-            ensure!(valid, "invalid proof");
-            Anchors::<T>::insert(&key, Revokable::Revoked);
-            Ok(())
+            let key = (admins.clone(), root.clone());
+            migration::migrate_anchor::<T>(&admins, &root);
+            ensure!(
+                Anchors::<T>::get(&admins, &root) != Some(Revokable::Revoked),
+                Error::<T>::AlreadyRevoked
+            );
+            ensure!(!AdminRotations::<T>::contains_key(&key), Error::<T>::AnchorRotated);
+            ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Revoker))?;
+            let creates = !Anchors::<T>::contains_key(&admins, &root);
+            let fee = Self::revocation_fee(&admins, creates);
+            if creates {
+                Self::index_anchor(&admins, &root);
+            }
+            Anchors::<T>::insert(&admins, &root, Revokable::Revoked);
+            Ok(fee.into())
         }
 
         /// revoke leaf until suspend_end. If suspend_end is in the past, this has no sematic
@@ -130,264 +610,3505 @@ decl_module! {
         ///
         /// The presence of a suspension in chain-state indicates that a member of the `admins`
         /// did suspend the 'leaf' until suspend_end.
+        ///
+        /// Extending a suspension is free if `admins` owns anchors, see `revocation_fee`. New
+        /// suspensions and failed attempts are charged.
+        #[weight = BASE_WEIGHT + proof.weight()]
         pub fn suspend_leaf(
             origin,
-            proof: Vec<ProofElement<T::TreeHashOut>>,
+            proof: AdminProof<T::TreeHashOut>,
             admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
             leaf: Hashed<Document, T::TreeHashOut>,
             suspend_end: UnixTimeSeconds,
-        ) -> DispatchResult {
+        ) -> DispatchResultWithPostInfo {
             let sender = ensure_signed(origin)?;
-            let key = (admins.clone(), leaf);
-            let current_suspend_end = SuspendedLeaves::<T>::get(&key);
+            migration::migrate_suspension::<T>(&admins, &leaf);
+            let current_suspend_end = SuspendedLeaves::<T>::get(&admins, &leaf);
             if let Some(end) = current_suspend_end {
-                ensure!(suspend_end > end, "leaf is already suspended until specified time");
+                ensure!(suspend_end > end, Error::<T>::AlreadySuspended);
+            }
+            ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Suspender))?;
+            SuspendedLeaves::<T>::insert(&admins, &leaf, suspend_end);
+            Ok(Self::revocation_fee(&admins, current_suspend_end.is_none()).into())
+        }
+
+        /// Lift the suspension of `leaf` before it ends. Permanent revocations, suspensions
+        /// ending at u64::max(), can not be lifted.
+        #[weight = BASE_WEIGHT + proof.weight()]
+        pub fn reinstate_leaf(
+            origin,
+            proof: AdminProof<T::TreeHashOut>,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            leaf: Hashed<Document, T::TreeHashOut>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            migration::migrate_suspension::<T>(&admins, &leaf);
+            let end = SuspendedLeaves::<T>::get(&admins, &leaf).ok_or(Error::<T>::NotSuspended)?;
+            ensure!(end != UnixTimeSeconds::max_value(), Error::<T>::PermanentlyRevoked);
+            ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Reinstater))?;
+            SuspendedLeaves::<T>::remove(&admins, &leaf);
+            Ok(())
+        }
+
+        /// Anchor several roots under the same `admins` set in a single call.
+        ///
+        /// Either every root is anchored or none is. The call fails if any of the roots is
+        /// already anchored or if a root appears twice in `roots`.
+        #[weight = BASE_WEIGHT + PER_ITEM_WEIGHT * roots.len() as Weight]
+        fn create_anchors(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            roots: Vec<MerkleRoot<Document, T::TreeHashOut>>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin.clone()).ok();
+            T::AnchorOrigin::ensure_origin(origin)?;
+            ensure!(roots.len() <= MAX_BATCH_SIZE, Error::<T>::BatchTooLarge);
+            for (i, root) in roots.iter().enumerate() {
+                ensure!(!roots[..i].contains(root), Error::<T>::DuplicateInBatch);
+                ensure!(!Self::is_anchored(&admins, root), Error::<T>::AlreadyAnchored);
+            }
+            Self::use_quota(sender.as_ref(), roots.len() as u32)?;
+            for root in roots {
+                Self::insert_anchor(admins.clone(), root);
             }
-            let valid = verify_proof::<T::TreeHash, T::AccountId, T::TreeHashOut>(
-                &admins,
-                &proof,
-                &hash_account_id::<T>(&sender)
+            Ok(())
+        }
+
+        /// First step of anchoring without revealing `root` before the anchor is included in a
+        /// block. `commitment` is `H(admins || root || salt)`, see `reveal_anchor`.
+        ///
+        /// The commitment must be revealed by the same account within `T::RevealWindow` blocks,
+        /// or it is removed.
+        #[weight = BASE_WEIGHT]
+        fn commit_anchor(
+            origin,
+            commitment: Hashed<AnchorCommitment, T::TreeHashOut>,
+        ) -> DispatchResult {
+            T::AnchorOrigin::ensure_origin(origin.clone())?;
+            let sender = ensure_signed(origin)?;
+            ensure!(
+                !AnchorCommitments::<T>::contains_key(&sender, &commitment),
+                Error::<T>::CommitmentExists
+            );
+            let deadline = <system::Module<T>>::block_number() + T::RevealWindow::get();
+            let mut expiring = CommitmentDeadlines::<T>::get(deadline);
+            ensure!(
+                expiring.len() < T::MaxCommitmentsPerBlock::get() as usize,
+                Error::<T>::CommitmentQuotaExceeded
             );
-            ensure!(valid, "invalid proof");
-            SuspendedLeaves::<T>::insert(key, suspend_end);
+            AnchorCommitments::<T>::insert(&sender, &commitment, deadline);
+            expiring.push((sender, commitment));
+            CommitmentDeadlines::<T>::insert(deadline, expiring);
             Ok(())
         }
-    }
-}
 
-fn hash_account_id<T: Trait>(
-    preimage: &<T as system::Trait>::AccountId,
-) -> Hashed<<T as system::Trait>::AccountId, T::TreeHashOut> {
-    let mut hasher = T::TreeHash::new();
-    T::hash_account_id(&preimage, &mut hasher);
-    Hashed::prehashed(T::TreeHashOut::from_alt(hasher.result()))
-}
+        /// Second step of anchoring started with `commit_anchor`. Anchors `root` under `admins`
+        /// exactly like `create_anchor` would.
+        #[weight = BASE_WEIGHT]
+        fn reveal_anchor(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            root: MerkleRoot<Document, T::TreeHashOut>,
+            salt: [u8; 32],
+        ) -> DispatchResult {
+            T::AnchorOrigin::ensure_origin(origin.clone())?;
+            let sender = ensure_signed(origin)?;
+            let commitment = anchor_commitment::<T>(&admins, &root, &salt);
+            let deadline = AnchorCommitments::<T>::get(&sender, &commitment)
+                .ok_or(Error::<T>::NoSuchCommitment)?;
+            ensure!(
+                <system::Module<T>>::block_number() <= deadline,
+                Error::<T>::RevealWindowClosed
+            );
+            ensure!(!Self::is_anchored(&admins, &root), Error::<T>::AlreadyAnchored);
+            Self::use_quota(Some(&sender), 1)?;
+            AnchorCommitments::<T>::remove(&sender, &commitment);
+            Self::insert_anchor(admins, root);
+            Ok(())
+        }
 
-impl<T: Trait> Module<T> {
-    pub fn lookup_anchor(
-        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
-        root: &MerkleRoot<Document, T::TreeHashOut>,
-    ) -> Option<Revokable<T::BlockNumber>> {
-        Anchors::<T>::get((auths, root))
-    }
+        /// Sparse merkle trees read hashes as paths of `sparse::DEPTH` bits, so a `TreeHashOut`
+        /// of any other length would make every revocation registry proof fail.
+        fn integrity_test() {
+            let hash = T::TreeHashOut::from_alt(T::TreeHash::digest(&[]));
+            assert_eq!(
+                hash.as_ref().len() * 8,
+                sparse::DEPTH,
+                "TreeHashOut must be {} bytes long",
+                sparse::DEPTH / 8,
+            );
+        }
 
-    /// Check if there is an active suspension on `leaf` issued by the the `auths` set.
-    /// Only members of the `auths` set with proof of membership may issue such a suspension.
-    pub fn leaf_suspended_by(
-        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
-        leaf: &Hashed<Document, T::TreeHashOut>,
-        now: UnixTimeSeconds,
-    ) -> bool {
-        match SuspendedLeaves::<T>::get((auths, leaf)) {
-            None => false,
-            Some(suspension_end) => now <= suspension_end,
+        /// Bring storage written by earlier versions of this pallet up to date.
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate::<T>()
         }
-    }
-}
 
-decl_event!(
-    pub enum Event<T>
-    where
-        <T as system::Trait>::AccountId,
-    {
-        Dummy(AccountId),
-    }
-);
+        /// Remove the commitments whose reveal window closed with the previous block and the
+        /// quota entries of accounts that stopped anchoring, and continue a storage migration
+        /// that did not finish in earlier blocks.
+        fn on_initialize(n: T::BlockNumber) -> Weight {
+            let migrated = migration::park_v1_entries::<T>();
+            let window = T::QuotaWindow::get();
+            let closed = n.saturating_sub(One::one());
+            let expiring = CommitmentDeadlines::<T>::take(closed);
+            let mut removed = 0;
+            for (committer, commitment) in &expiring {
+                // The commitment may have been revealed and then made again with a later
+                // deadline.
+                if AnchorCommitments::<T>::get(committer, commitment) == Some(closed) {
+                    AnchorCommitments::<T>::remove(committer, commitment);
+                    removed += 1;
+                }
+            }
+            let idle = QuotaExpiries::<T>::take(n);
+            for who in &idle {
+                let recent = RecentAnchors::<T>::get(who);
+                if recent.last().map_or(true, |(block, _)| block.saturating_add(window) <= n) {
+                    RecentAnchors::<T>::remove(who);
+                }
+            }
+            migrated
+                + T::DbWeight::get().reads_writes(
+                    2 + expiring.len() as Weight + idle.len() as Weight,
+                    2 + removed + idle.len() as Weight,
+                )
+        }
 
-/// tests for this pallet
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use blake2::Blake2s;
-    use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
-    use sp_core::H256;
-    use sp_runtime::{
-        testing::Header,
-        traits::{BlakeTwo256, IdentityLookup},
-        Perbill,
-    };
+        /// Suspend several leaves, each until its own suspend_end, with a single proof of
+        /// membership in `admins`.
+        ///
+        /// Either every suspension is applied or none is. Each suspension is subject to the same
+        /// rules as in `suspend_leaf`, and a leaf may appear at most once in `suspensions`. Fees
+        /// are waived as for `suspend_leaf` if every leaf is suspended already.
+        #[weight = BASE_WEIGHT + proof.weight() + PER_ITEM_WEIGHT * suspensions.len() as Weight]
+        pub fn suspend_leaves(
+            origin,
+            proof: AdminProof<T::TreeHashOut>,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            suspensions: Vec<(Hashed<Document, T::TreeHashOut>, UnixTimeSeconds)>,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(suspensions.len() <= MAX_BATCH_SIZE, Error::<T>::BatchTooLarge);
+            let mut creates = false;
+            for (i, (leaf, suspend_end)) in suspensions.iter().enumerate() {
+                ensure!(
+                    suspensions[..i].iter().all(|(l, _)| l != leaf),
+                    Error::<T>::DuplicateInBatch
+                );
+                migration::migrate_suspension::<T>(&admins, leaf);
+                match SuspendedLeaves::<T>::get(&admins, leaf) {
+                    Some(end) => ensure!(*suspend_end > end, Error::<T>::AlreadySuspended),
+                    None => creates = true,
+                }
+            }
+            ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Suspender))?;
+            for (leaf, suspend_end) in suspensions {
+                SuspendedLeaves::<T>::insert(&admins, leaf, suspend_end);
+            }
+            Ok(Self::revocation_fee(&admins, creates).into())
+        }
 
-    // Test module shorthand
-    type Tm = Module<Test>;
+        /// Revoke and reinstate leaves in the revocation registry of `admins`, a sparse merkle
+        /// tree of revoked leaves of which only the root is kept on-chain.
+        ///
+        /// Each update is a leaf, its sparse merkle proof and whether the leaf is to be revoked
+        /// or reinstated. Updates are applied in order, so the proof of an update is against the
+        /// root left by the updates before it. At least one update is needed. Revoking needs the
+        /// `Suspender` role, reinstating the `Reinstater` role. Fees are waived as for
+        /// `suspend_leaves`, except for the first update, which creates the registry.
+        ///
+        /// This is independent of `SuspendedLeaves`. Holders prove a leaf is not revoked with a
+        /// non-membership proof against `revocation_root`, see `revoked_in_registry`.
+        ///
+        /// Weighed by the hashes computed: one table of empty subtrees for the call, then
+        /// `sparse::UPDATE_HASHES` for each update.
+        #[weight = BASE_WEIGHT + proof.weight() + PER_HASH_WEIGHT
+            * (sparse::DEPTH + sparse::UPDATE_HASHES * updates.len()) as Weight]
+        pub fn update_revocation_root(
+            origin,
+            proof: AdminProof<T::TreeHashOut>,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            updates: Vec<(Hashed<Document, T::TreeHashOut>, SparseProof<T::TreeHashOut>, bool)>,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure!(!updates.is_empty(), Error::<T>::EmptyBatch);
+            ensure!(updates.len() <= MAX_BATCH_SIZE, Error::<T>::BatchTooLarge);
+            // at least one of the roles is checked, whatever the updates are
+            let revokes = updates.iter().any(|(_, _, revoke)| *revoke);
+            if revokes {
+                ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Suspender))?;
+            }
+            if !revokes || updates.iter().any(|(_, _, revoke)| !revoke) {
+                ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Reinstater))?;
+            }
+            let empty = EmptySubtrees::new::<T::TreeHash>();
+            let stored = RevocationRoots::<T>::get(&admins);
+            let creates = stored.is_none();
+            let mut root = stored.unwrap_or_else(|| empty.root());
+            for (leaf, leaf_proof, revoke) in &updates {
+                root = sparse::update_root::<T::TreeHash, _, _>(
+                    &root,
+                    leaf,
+                    leaf_proof,
+                    !revoke,
+                    *revoke,
+                    &empty,
+                )
+                .ok_or(Error::<T>::InvalidUpdateProof)?;
+            }
+            RevocationRoots::<T>::insert(&admins, &root);
+            Self::deposit_event(RawEvent::RevocationRootUpdated(admins.clone(), root));
+            Ok(Self::revocation_fee(&admins, creates).into())
+        }
 
-    impl_outer_origin! {
-        pub enum Origin for Test {}
-    }
+        /// Anchor `list`, the hash of a GZIP-compressed bitstring status list, as the current
+        /// status list of `admins`. The list itself is published off-chain.
+        ///
+        /// A new list may revoke some credentials and reinstate others, so only unscoped admin
+        /// leaves may publish one. Fees are waived as for `suspend_leaves`, except for the first
+        /// list of `admins`.
+        #[weight = BASE_WEIGHT + proof.weight()]
+        pub fn update_status_list(
+            origin,
+            proof: AdminProof<T::TreeHashOut>,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            list: Hashed<StatusList, T::TreeHashOut>,
+        ) -> DispatchResultWithPostInfo {
+            let sender = ensure_signed(origin)?;
+            ensure_admin::<T>(&admins, &proof, &sender, None)?;
+            let pays = Self::revocation_fee(&admins, !StatusLists::<T>::contains_key(&admins));
+            StatusLists::<T>::insert(&admins, &list);
+            Self::deposit_event(RawEvent::StatusListUpdated(admins, list));
+            Ok(pays.into())
+        }
 
-    /// Hash using Blake2s
-    fn blake(x: impl Hash) -> [u8; 32] {
-        Hashed::from_preimage::<Blake2s>(&x).hash
-    }
+        /// Move an anchor from `old_admins` to `new_admins`. Any member of `old_admins` may do
+        /// this. Afterwards only members of `new_admins` are able to revoke the anchor.
+        ///
+        /// Suspensions of the listed `leaves` are moved along with the anchor. Suspensions are
+        /// not tied to an anchor on-chain so they have to be listed explicitly.
+        ///
+        /// The anchor stays reachable through `old_admins` in `lookup_anchor`. A root can only be
+        /// rotated to an admin set it was never anchored under, so rotations never form a cycle.
+        #[weight = BASE_WEIGHT + proof.weight() + PER_ITEM_WEIGHT * leaves.len() as Weight]
+        fn rotate_admins(
+            origin,
+            old_admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            new_admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            root: MerkleRoot<Document, T::TreeHashOut>,
+            proof: AdminProof<T::TreeHashOut>,
+            leaves: Vec<Hashed<Document, T::TreeHashOut>>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(leaves.len() <= MAX_BATCH_SIZE, Error::<T>::BatchTooLarge);
+            let old_key = (old_admins.clone(), root.clone());
+            let new_key = (new_admins.clone(), root.clone());
+            migration::migrate_anchor::<T>(&old_admins, &root);
+            let anchor = Anchors::<T>::get(&old_admins, &root).ok_or(Error::<T>::NoSuchAnchor)?;
+            ensure!(anchor != Revokable::Revoked, Error::<T>::AlreadyRevoked);
+            ensure!(
+                !Self::is_anchored(&new_admins, &root),
+                Error::<T>::AlreadyAnchored
+            );
+            for (i, leaf) in leaves.iter().enumerate() {
+                ensure!(!leaves[..i].contains(leaf), Error::<T>::DuplicateInBatch);
+                migration::migrate_suspension::<T>(&old_admins, leaf);
+                migration::migrate_suspension::<T>(&new_admins, leaf);
+                ensure!(
+                    SuspendedLeaves::<T>::contains_key(&old_admins, leaf),
+                    Error::<T>::NotSuspended
+                );
+            }
+            ensure_admin::<T>(&old_admins, &proof, &sender, None)?;
 
-    // For testing the pallet, we construct most of a mock runtime. This means
-    // first constructing a configuration type (`Test`) which `impl`s each of the
-    // configuration traits of modules we want to use.
-    #[derive(Clone, Eq, PartialEq)]
-    pub struct Test;
-    parameter_types! {
-        pub const BlockHashCount: u64 = 250;
-        pub const MaximumBlockWeight: Weight = 1024;
-        pub const MaximumBlockLength: u32 = 2 * 1024;
-        pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
-    }
-    impl system::Trait for Test {
-        type Origin = Origin;
-        type Call = ();
-        type Index = u64;
-        type BlockNumber = u64;
-        type Hash = H256;
-        type Hashing = BlakeTwo256;
-        type AccountId = u64;
-        type Lookup = IdentityLookup<Self::AccountId>;
+            for leaf in leaves {
+                let end = SuspendedLeaves::<T>::take(&old_admins, &leaf).unwrap_or_default();
+                SuspendedLeaves::<T>::mutate(&new_admins, &leaf, |current| {
+                    *current = Some(current.map_or(end, |c| c.max(end)));
+                });
+            }
+            Anchors::<T>::remove(&old_admins, &root);
+            Self::unindex_anchor(&old_admins, &root);
+            Anchors::<T>::insert(&new_admins, &root, anchor);
+            Self::index_anchor(&new_admins, &root);
+            if let Some(validity) = AnchorValidity::<T>::take(&old_key) {
+                AnchorValidity::<T>::insert(&new_key, validity);
+            }
+            if let Some(previous) = PreviousVersions::<T>::take(&old_key) {
+                PreviousVersions::<T>::insert(&new_key, previous);
+            }
+            AdminRotations::<T>::insert(
+                &old_key,
+                (new_admins.clone(), <system::Module<T>>::block_number()),
+            );
+            RotatedFrom::<T>::insert(&new_key, &old_admins);
+            Self::deposit_event(RawEvent::AdminsRotated(old_admins, new_admins, root));
+            Ok(())
+        }
+
+        /// Revoke the anchor of `old_root` and anchor `new_root` in its place, recording that
+        /// `new_root` replaces `old_root`. Any member of `admins` authorized to revoke may do this.
+        ///
+        /// `old_root` must be anchored under `admins` and not superseded already. It may already
+        /// be revoked. `new_root` must never have been anchored under `admins`, so following
+        /// supersessions always ends.
+        #[weight = BASE_WEIGHT + proof.weight()]
+        fn supersede_anchor(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            old_root: MerkleRoot<Document, T::TreeHashOut>,
+            new_root: MerkleRoot<Document, T::TreeHashOut>,
+            proof: AdminProof<T::TreeHashOut>,
+        ) -> DispatchResult {
+            T::AnchorOrigin::ensure_origin(origin.clone())?;
+            let sender = ensure_signed(origin)?;
+            let old_key = (admins.clone(), old_root.clone());
+            migration::migrate_anchor::<T>(&admins, &old_root);
+            ensure!(Anchors::<T>::contains_key(&admins, &old_root), Error::<T>::NoSuchAnchor);
+            ensure!(!Supersessions::<T>::contains_key(&old_key), Error::<T>::AlreadySuperseded);
+            ensure!(!Self::is_anchored(&admins, &new_root), Error::<T>::AlreadyAnchored);
+            ensure_admin::<T>(&admins, &proof, &sender, Some(Role::Revoker))?;
+            Self::use_quota(Some(&sender), 1)?;
+            Anchors::<T>::insert(&admins, &old_root, Revokable::Revoked);
+            Supersessions::<T>::insert(&old_key, &new_root);
+            PreviousVersions::<T>::insert((&admins, &new_root), &old_key);
+            Self::insert_anchor(admins.clone(), new_root.clone());
+            Self::deposit_event(RawEvent::AnchorSuperseded(admins, old_root, new_root));
+            Ok(())
+        }
+
+        /// Vote to disable the admin leaf `target` of `admins`. Once `T::DisableThreshold`
+        /// distinct members of `admins` have voted, `target` can no longer be used to prove
+        /// membership in `admins`.
+        ///
+        /// Disabling is permanent. Disabled members may not vote.
+        #[weight = BASE_WEIGHT + proof.weight()]
+        fn disable_admin(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            proof: AdminProof<T::TreeHashOut>,
+            target: Hashed<T::AccountId, T::TreeHashOut>,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let key = (admins.clone(), target.clone());
+            ensure!(!DisabledAdmins::<T>::get(&key), Error::<T>::AdminAlreadyDisabled);
+            ensure_admin::<T>(&admins, &proof, &sender, None)?;
+            let voter = hash_account_id::<T>(&sender);
+            let mut votes = DisableVotes::<T>::get(&key);
+            ensure!(!votes.contains(&voter), Error::<T>::AlreadyVoted);
+            votes.push(voter);
+            if votes.len() >= T::DisableThreshold::get().max(1) as usize {
+                DisableVotes::<T>::remove(&key);
+                DisabledAdmins::<T>::insert(&key, true);
+                Self::deposit_event(RawEvent::AdminDisabled(admins, target));
+            } else {
+                DisableVotes::<T>::insert(&key, votes);
+            }
+            Ok(())
+        }
+
+        /// Allow `delegate` to act in `role` on behalf of `admins` until `expires`, without a proof
+        /// of membership. The sender must be authorized for `role` through a proof of membership
+        /// in `admins`. Delegates may not delegate further. A leaf with a validity period may not
+        /// delegate beyond the end of that period.
+        ///
+        /// A new delegation replaces the existing one for the same delegate and role. Passing an
+        /// `expires` in the past ends the existing delegation. Only the admin leaf that granted an
+        /// active delegation may replace or end it.
+        #[weight = BASE_WEIGHT + proof.weight()]
+        fn delegate(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            proof: AdminProof<T::TreeHashOut>,
+            delegate: T::AccountId,
+            role: Role,
+            expires: UnixTimeSeconds,
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let granted_by = ensure_member::<T>(&admins, &proof, &sender, Some(role))?;
+            if let Some(validity) = &proof.validity {
+                ensure!(
+                    expires <= validity.not_after,
+                    Error::<T>::DelegationTooLong
+                );
+            }
+            let key = (admins, delegate, role);
+            if let Some(existing) = Delegations::<T>::get(&key) {
+                let active = T::current_time() <= existing.expires
+                    && !DisabledAdmins::<T>::get((&key.0, &existing.granted_by));
+                ensure!(
+                    !active || existing.granted_by == granted_by,
+                    Error::<T>::DelegationNotOwned
+                );
+            }
+            if expires < T::current_time() {
+                Delegations::<T>::remove(&key);
+            } else {
+                Delegations::<T>::insert(&key, Delegation { expires, granted_by });
+            }
+            Ok(())
+        }
+
+        /// Move anchors and suspensions written by an earlier version of this pallet to the
+        /// current storage layout. See `migration` for how entries present in both layouts are
+        /// merged.
+        ///
+        /// `on_runtime_upgrade` keeps entries of the earlier layout under the hash of their key,
+        /// since the key itself can not be recovered, so the keys have to be listed here. Until
+        /// an entry is moved, it is looked up in both layouts and moved by the first call that
+        /// changes it.
+        #[weight = BASE_WEIGHT + PER_ITEM_WEIGHT * (anchors.len() + suspensions.len()) as Weight]
+        fn migrate_legacy_storage(
+            origin,
+            anchors: Vec<(
+                MerkleRoot<T::AccountId, T::TreeHashOut>,
+                MerkleRoot<Document, T::TreeHashOut>,
+            )>,
+            suspensions: Vec<(
+                MerkleRoot<T::AccountId, T::TreeHashOut>,
+                Hashed<Document, T::TreeHashOut>,
+            )>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            for (admins, root) in anchors {
+                migration::migrate_anchor::<T>(&admins, &root);
+            }
+            for (admins, leaf) in suspensions {
+                migration::migrate_suspension::<T>(&admins, &leaf);
+            }
+            Ok(())
+        }
+
+        /// Drop anchors and suspensions of an earlier storage layout that were not migrated, by
+        /// the hash they are kept under, see `migration`.
+        ///
+        /// Such entries are missing from `anchors_for_root`, `anchors_by_admins` and the fee
+        /// waiver of `revocation_fee`, and `snapshot::export` refuses to run while any is left.
+        /// This is the way out for entries whose key is lost for good. Dropped entries are gone,
+        /// lookups by their key no longer find them either.
+        #[weight = BASE_WEIGHT + PER_ITEM_WEIGHT * (anchors.len() + suspensions.len()) as Weight]
+        fn clear_legacy_entries(
+            origin,
+            anchors: Vec<[u8; 32]>,
+            suspensions: Vec<[u8; 32]>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(
+                anchors.len() + suspensions.len() <= MAX_BATCH_SIZE,
+                Error::<T>::BatchTooLarge
+            );
+            migration::clear::<T>(&anchors, &suspensions);
+            Ok(())
+        }
+
+        /// Revoke an anchor without proving membership in `admins`. This is the only way to
+        /// revoke an anchor made under the irrevocable, all zero, admin set.
+        #[weight = BASE_WEIGHT]
+        fn force_revoke_anchor(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            root: MerkleRoot<Document, T::TreeHashOut>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            migration::migrate_anchor::<T>(&admins, &root);
+            match Anchors::<T>::get(&admins, &root) {
+                None => return Err(Error::<T>::NoSuchAnchor.into()),
+                Some(Revokable::Revoked) => return Err(Error::<T>::AlreadyRevoked.into()),
+                Some(Revokable::NotRevoked(_)) => {}
+            }
+            Anchors::<T>::insert(&admins, &root, Revokable::Revoked);
+            Self::deposit_event(RawEvent::AnchorForceRevoked(admins, root));
+            Ok(())
+        }
+
+        /// Suspend `leaf` on behalf of `admins` until `suspend_end` without proving membership
+        /// in `admins`. Like `suspend_leaf`, a suspension can only be extended.
+        #[weight = BASE_WEIGHT]
+        fn force_suspend_leaf(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            leaf: Hashed<Document, T::TreeHashOut>,
+            suspend_end: UnixTimeSeconds,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            migration::migrate_suspension::<T>(&admins, &leaf);
+            if let Some(end) = SuspendedLeaves::<T>::get(&admins, &leaf) {
+                ensure!(suspend_end > end, Error::<T>::AlreadySuspended);
+            }
+            SuspendedLeaves::<T>::insert(&admins, &leaf, suspend_end);
+            Self::deposit_event(RawEvent::LeafForceSuspended(admins, leaf, suspend_end));
+            Ok(())
+        }
+
+        /// Erase an anchor, revoked or not, from chain-state. Once removed, the root is no longer
+        /// known under `admins` and may be anchored again. Supersessions from and to the root are
+        /// erased as well, and so are the rotations that led to `admins`, so the root is no longer
+        /// known under the admin sets it was rotated away from either.
+        #[weight = BASE_WEIGHT]
+        fn force_remove(
+            origin,
+            admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+            root: MerkleRoot<Document, T::TreeHashOut>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            migration::migrate_anchor::<T>(&admins, &root);
+            ensure!(Anchors::<T>::contains_key(&admins, &root), Error::<T>::NoSuchAnchor);
+            Anchors::<T>::remove(&admins, &root);
+            AnchorValidity::<T>::remove((&admins, &root));
+            Self::unindex_anchor(&admins, &root);
+            // neither the root nor the version it replaced are superseded any more
+            if let Some(new_root) = Supersessions::<T>::take((&admins, &root)) {
+                let new_admins = Self::current_admins(&admins, &new_root);
+                PreviousVersions::<T>::remove((&new_admins, &new_root));
+            }
+            if let Some(previous) = PreviousVersions::<T>::take((&admins, &root)) {
+                Supersessions::<T>::remove(&previous);
+            }
+            let mut current = admins.clone();
+            while let Some(previous) = RotatedFrom::<T>::take((&current, &root)) {
+                AdminRotations::<T>::remove((&previous, &root));
+                current = previous;
+            }
+            Self::deposit_event(RawEvent::AnchorForceRemoved(admins, root));
+            Ok(())
+        }
+
+        /// Import the anchoring data of a snapshot exported from another chain. See `snapshot`
+        /// for how imported entries are merged with existing ones.
+        ///
+        /// The whole snapshot is refused if its checksum does not match its entries, or if one
+        /// of its rotations does not lead to an anchor or would form a cycle.
+        #[weight = BASE_WEIGHT + PER_ITEM_WEIGHT * snapshot.len() as Weight]
+        fn import_anchors(origin, snapshot: SnapshotOf<T>) -> DispatchResult {
+            ensure_root(origin)?;
+            snapshot::import::<T>(&snapshot)?;
+            Ok(())
+        }
+    }
+}
+
+/// Check that `account` may act on behalf of `admins` in `role`, either as a member of `admins`
+/// (see `ensure_member`) or through an active delegation for `role`.
+///
+/// `role` being `None` means the action is reserved to unscoped members, delegations are never
+/// accepted for such actions.
+fn ensure_admin<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    proof: &AdminProof<T::TreeHashOut>,
+    account: &T::AccountId,
+    role: Option<Role>,
+) -> DispatchResult {
+    let membership = ensure_member::<T>(admins, proof, account, role);
+    let (e, role) = match (membership, role) {
+        (Ok(_), _) => return Ok(()),
+        (Err(e), None) => return Err(e),
+        (Err(e), Some(role)) => (e, role),
+    };
+    // Without a delegation, the reason the proof was refused is the relevant error.
+    let delegation = Delegations::<T>::get((admins, account, role)).ok_or(e)?;
+    ensure!(
+        T::current_time() <= delegation.expires,
+        Error::<T>::DelegationExpired
+    );
+    ensure!(
+        !DisabledAdmins::<T>::get((admins, &delegation.granted_by)),
+        Error::<T>::AdminDisabled
+    );
+    Ok(())
+}
+
+/// Check that `proof` proves membership of an admin leaf for `account` in `admins` and that the
+/// leaf is not disabled. If the leaf has a validity period, the current time must be within it.
+/// Returns the proven leaf. Proofs longer than `MAX_PROOF_SIZE` are refused before hashing.
+///
+/// Unscoped leaves are accepted for any role. Leaves scoped to `role` are accepted as well, unless
+/// `role` is `None`, which means the action is reserved to unscoped leaves.
+fn ensure_member<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    proof: &AdminProof<T::TreeHashOut>,
+    account: &T::AccountId,
+    role: Option<Role>,
+) -> Result<Hashed<T::AccountId, T::TreeHashOut>, DispatchError> {
+    ensure!(
+        proof.path.hashes.len() <= MAX_PROOF_SIZE,
+        Error::<T>::ProofTooLarge
+    );
+    let validity = proof.validity.as_ref();
+    let unscoped = Some(hash_admin_leaf::<T>(account, None, validity));
+    let scoped = role.map(|role| hash_admin_leaf::<T>(account, Some(role), validity));
+    let leaf = unscoped
+        .into_iter()
+        .chain(scoped)
+        .find(|leaf| {
+            verify_compact_proof::<T::TreeHash, T::AccountId, T::TreeHashOut>(
+                admins,
+                &proof.path,
+                leaf,
+            )
+        })
+        .ok_or(Error::<T>::InvalidProof)?;
+    if let Some(validity) = validity {
+        ensure!(
+            validity.contains(T::current_time()),
+            Error::<T>::AdminNotValid
+        );
+    }
+    ensure!(
+        !DisabledAdmins::<T>::get((admins, &leaf)),
+        Error::<T>::AdminDisabled
+    );
+    Ok(leaf)
+}
+
+/// Hash the admin leaf of `account`, optionally scoped to `role` and limited to `validity`.
+fn hash_admin_leaf<T: Trait>(
+    account: &<T as system::Trait>::AccountId,
+    role: Option<Role>,
+    validity: Option<&Validity>,
+) -> Hashed<<T as system::Trait>::AccountId, T::TreeHashOut> {
+    let mut hasher = T::TreeHash::new();
+    match role {
+        Some(role) => T::hash_admin_role(account, role, &mut hasher),
+        None => T::hash_account_id(account, &mut hasher),
+    }
+    if let Some(validity) = validity {
+        validity.hash(&mut hasher);
+    }
+    Hashed::prehashed(T::TreeHashOut::from_alt(hasher.result()))
+}
+
+fn anchor_commitment<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    root: &MerkleRoot<Document, T::TreeHashOut>,
+    salt: &[u8; 32],
+) -> Hashed<AnchorCommitment, T::TreeHashOut> {
+    let mut hasher = T::TreeHash::new();
+    (admins, root, salt).hash(&mut hasher);
+    Hashed::prehashed(T::TreeHashOut::from_alt(hasher.result()))
+}
+
+fn hash_account_id<T: Trait>(
+    preimage: &<T as system::Trait>::AccountId,
+) -> Hashed<<T as system::Trait>::AccountId, T::TreeHashOut> {
+    let mut hasher = T::TreeHash::new();
+    T::hash_account_id(&preimage, &mut hasher);
+    Hashed::prehashed(T::TreeHashOut::from_alt(hasher.result()))
+}
+
+impl<T: Trait> Module<T> {
+    /// Get the status of an anchor at time `now`. If the anchor was rotated away from `auths`,
+    /// the status is looked up under the admin set it currently belongs to.
+    pub fn lookup_anchor(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+        now: UnixTimeSeconds,
+    ) -> Option<AnchorStatus<T::BlockNumber>> {
+        let admins = Self::current_admins(auths, root);
+        let block = match migration::anchor::<T>(&admins, root)? {
+            Revokable::Revoked => return Some(AnchorStatus::Revoked),
+            Revokable::NotRevoked(block) => block,
+        };
+        Some(match AnchorValidity::<T>::get((&admins, root)) {
+            Some(validity) if now < validity.not_before => AnchorStatus::NotYetValid(block),
+            Some(validity) if validity.not_after < now => AnchorStatus::Expired(block),
+            _ => AnchorStatus::Valid(block),
+        })
+    }
+
+    /// Get the status of the document `leaf` at time `now`, given a proof that it is part of
+    /// `root` and `root` is anchored under `auths`.
+    ///
+    /// Suspensions of `leaf` are looked up both under `auths` and under the admin set that
+    /// currently controls `root`.
+    pub fn verify_document(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+        proof: &[ProofElement<T::TreeHashOut>],
+        leaf: &Hashed<Document, T::TreeHashOut>,
+        now: UnixTimeSeconds,
+    ) -> DocumentStatus {
+        if !verify_proof::<T::TreeHash, Document, T::TreeHashOut>(root, proof, leaf) {
+            return DocumentStatus::NotIncluded;
+        }
+        Self::included_document_status(auths, root, leaf, now)
+    }
+
+    /// Get the status of each of the documents `leaves` at time `now`, given a single proof that
+    /// all of them are part of `root`. See `verify_document`.
+    ///
+    /// If the proof is invalid, every document is reported as `NotIncluded`.
+    pub fn verify_documents(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+        proof: &MultiProof<T::TreeHashOut>,
+        leaves: &[Hashed<Document, T::TreeHashOut>],
+        now: UnixTimeSeconds,
+    ) -> Vec<DocumentStatus> {
+        if !verify_multiproof::<T::TreeHash, Document, T::TreeHashOut>(root, proof, leaves) {
+            return leaves.iter().map(|_| DocumentStatus::NotIncluded).collect();
+        }
+        leaves
+            .iter()
+            .map(|leaf| Self::included_document_status(auths, root, leaf, now))
+            .collect()
+    }
+
+    /// The status of `leaf`, known to be part of `root`, at time `now`.
+    fn included_document_status(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+        leaf: &Hashed<Document, T::TreeHashOut>,
+        now: UnixTimeSeconds,
+    ) -> DocumentStatus {
+        match Self::lookup_anchor(auths, root, now) {
+            None => return DocumentStatus::NotAnchored,
+            Some(AnchorStatus::NotYetValid(_)) => return DocumentStatus::NotYetValid,
+            Some(AnchorStatus::Expired(_)) => return DocumentStatus::Expired,
+            Some(AnchorStatus::Revoked) => return DocumentStatus::Revoked,
+            Some(AnchorStatus::Valid(_)) => {}
+        }
+        let current = Self::current_admins(auths, root);
+        if Self::leaf_suspended_by(auths, leaf, now) || Self::leaf_suspended_by(&current, leaf, now)
+        {
+            return DocumentStatus::Suspended;
+        }
+        DocumentStatus::Valid
+    }
+
+    /// Follow the rotations of `root` starting at `auths` and return the admin set that
+    /// currently controls it. Returns `auths` if the root was never rotated away from it.
+    pub fn current_admins(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) -> MerkleRoot<T::AccountId, T::TreeHashOut> {
+        let mut current = auths.clone();
+        while let Some((next, _)) = AdminRotations::<T>::get((&current, root)) {
+            current = next;
+        }
+        current
+    }
+
+    /// Follow the supersessions of `root` starting at `auths` and return the root of the latest
+    /// version. Rotations are followed along the way. Returns `root` if it was never superseded.
+    ///
+    /// The latest version may itself be revoked without replacement, check it with
+    /// `lookup_anchor`.
+    pub fn current_version(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) -> MerkleRoot<Document, T::TreeHashOut> {
+        let mut current = root.clone();
+        let mut admins = Self::current_admins(auths, &current);
+        while let Some(next) = Supersessions::<T>::get((&admins, &current)) {
+            current = next;
+            admins = Self::current_admins(&admins, &current);
+        }
+        current
+    }
+
+    /// The fee for a successful revocation or suspension on behalf of `admins`, `creates` telling
+    /// whether the call adds a storage entry that did not exist before. Waived only if it does
+    /// not and `admins` owns anchors, otherwise anyone could make up an admin set of their own,
+    /// or use one they paid an anchor for once, and fill storage for free. Revocations must ask
+    /// before writing to `Anchors`.
+    ///
+    /// Anchors of an earlier storage layout that were not migrated yet do not count, they can not
+    /// be found by admin set.
+    fn revocation_fee(admins: &MerkleRoot<T::AccountId, T::TreeHashOut>, creates: bool) -> Pays {
+        if !creates && Anchors::<T>::iter_prefix(admins).next().is_some() {
+            Pays::No
+        } else {
+            Pays::Yes
+        }
+    }
+
+    /// Whether `root` was ever anchored or revoked under `admins`, including anchors that were
+    /// rotated away from `admins` since.
+    fn is_anchored(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) -> bool {
+        migration::anchor::<T>(admins, root).is_some()
+            || AdminRotations::<T>::contains_key((admins, root))
+    }
+
+    /// Anchor `root` under `admins` at the current block. The caller must check `is_anchored`.
+    fn insert_anchor(
+        admins: MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: MerkleRoot<Document, T::TreeHashOut>,
+    ) {
+        Anchors::<T>::insert(
+            &admins,
+            &root,
+            Revokable::NotRevoked(<system::Module<T>>::block_number()),
+        );
+        Self::index_anchor(&admins, &root);
+        Self::deposit_event(RawEvent::AnchorCreated(admins, root));
+    }
+
+    /// Count `count` new anchors against the quota of this block and, for signed origins, against
+    /// the quota of `who`. Nothing is counted if either quota would be exceeded.
+    fn use_quota(who: Option<&T::AccountId>, count: u32) -> DispatchResult {
+        if count == 0 {
+            return Ok(());
+        }
+        let now = <system::Module<T>>::block_number();
+        let (block, used) = BlockAnchors::<T>::get();
+        let used = if block == now { used } else { 0 };
+        let used = used
+            .checked_add(count)
+            .filter(|used| *used <= T::MaxAnchorsPerBlock::get())
+            .ok_or(Error::<T>::BlockQuotaExceeded)?;
+        if let Some(who) = who {
+            let window = T::QuotaWindow::get();
+            let mut recent = RecentAnchors::<T>::get(who);
+            recent.retain(|(block, _)| block.saturating_add(window) > now);
+            let in_window = recent
+                .iter()
+                .try_fold(count, |total, (_, n)| total.checked_add(*n))
+                .filter(|total| *total <= T::MaxAnchorsPerAccount::get());
+            ensure!(in_window.is_some(), Error::<T>::AccountQuotaExceeded);
+            match recent.last_mut() {
+                Some((block, n)) if *block == now => *n += count,
+                _ => {
+                    recent.push((now, count));
+                    QuotaExpiries::<T>::mutate(now.saturating_add(window), |accounts| {
+                        accounts.push(who.clone())
+                    });
+                }
+            }
+            RecentAnchors::<T>::insert(who, recent);
+        }
+        BlockAnchors::<T>::put((now, used));
+        Ok(())
+    }
+
+    /// Record that `root` has an entry under `admins` in `Anchors`.
+    fn index_anchor(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) {
+        AnchorsByRoot::<T>::insert(root, admins, ());
+    }
+
+    /// Record that `root` no longer has an entry under `admins` in `Anchors`.
+    fn unindex_anchor(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) {
+        AnchorsByRoot::<T>::remove(root, admins);
+    }
+
+    /// The admin sets `root` is anchored or revoked under, in no particular order. Admin sets an
+    /// anchor was rotated away from, or that hold anchors not migrated from an earlier storage
+    /// layout yet, are not included.
+    pub fn anchors_for_root(
+        root: &MerkleRoot<Document, T::TreeHashOut>,
+    ) -> Vec<MerkleRoot<T::AccountId, T::TreeHashOut>> {
+        AnchorsByRoot::<T>::iter_prefix(root)
+            .map(|(admins, ())| admins)
+            .collect()
+    }
+
+    /// All roots anchored or revoked under `admins`, not including anchors that were rotated
+    /// away from `admins` or that were not migrated from an earlier storage layout yet.
+    pub fn anchors_by_admins(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    ) -> Vec<(
+        MerkleRoot<Document, T::TreeHashOut>,
+        Revokable<T::BlockNumber>,
+    )> {
+        Anchors::<T>::iter_prefix(admins).collect()
+    }
+
+    /// All suspensions issued by `admins`, including the ones that ended already. Suspensions
+    /// that were not migrated from an earlier storage layout yet are not included.
+    pub fn suspensions_by_admins(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    ) -> Vec<(Hashed<Document, T::TreeHashOut>, UnixTimeSeconds)> {
+        SuspendedLeaves::<T>::iter_prefix(admins).collect()
+    }
+
+    /// Root of the revocation registry of `admins`, the empty tree if `admins` never revoked a
+    /// leaf through `update_revocation_root`.
+    pub fn revocation_root(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    ) -> SparseMerkleRoot<Document, T::TreeHashOut> {
+        RevocationRoots::<T>::get(admins).unwrap_or_else(SparseMerkleRoot::empty::<T::TreeHash>)
+    }
+
+    /// Check `leaf` against the revocation registry of `auths` using a proof from the holder.
+    /// Some(true) if `proof` shows the leaf is revoked, Some(false) if it shows the leaf is not
+    /// revoked and None if the proof is not valid for the current root.
+    pub fn revoked_in_registry(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        leaf: &Hashed<Document, T::TreeHashOut>,
+        proof: &SparseProof<T::TreeHashOut>,
+    ) -> Option<bool> {
+        let empty = EmptySubtrees::new::<T::TreeHash>();
+        let root = RevocationRoots::<T>::get(auths).unwrap_or_else(|| empty.root());
+        if sparse::verify_membership::<T::TreeHash, _, _>(&root, leaf, proof, &empty) {
+            Some(true)
+        } else if sparse::verify_non_membership::<T::TreeHash, _, _>(&root, leaf, proof, &empty) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Hash of the current status list of `admins`, if it published one.
+    pub fn status_list(
+        admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    ) -> Option<Hashed<StatusList, T::TreeHashOut>> {
+        StatusLists::<T>::get(admins)
+    }
+
+    /// Check if there is an active suspension on `leaf` issued by the the `auths` set.
+    /// Only members of the `auths` set with proof of membership may issue such a suspension.
+    pub fn leaf_suspended_by(
+        auths: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+        leaf: &Hashed<Document, T::TreeHashOut>,
+        now: UnixTimeSeconds,
+    ) -> bool {
+        match migration::suspension::<T>(auths, leaf) {
+            None => false,
+            Some(suspension_end) => now <= suspension_end,
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        Admins = MerkleRoot<<T as system::Trait>::AccountId, <T as Trait>::TreeHashOut>,
+        Root = MerkleRoot<Document, <T as Trait>::TreeHashOut>,
+        AdminLeaf = Hashed<<T as system::Trait>::AccountId, <T as Trait>::TreeHashOut>,
+        Leaf = Hashed<Document, <T as Trait>::TreeHashOut>,
+        SparseRoot = SparseMerkleRoot<Document, <T as Trait>::TreeHashOut>,
+        StatusListHash = Hashed<StatusList, <T as Trait>::TreeHashOut>,
+    {
+        /// A root was anchored under an admin set.
+        AnchorCreated(Admins, Root),
+        /// An anchor was moved from the first admin set to the second.
+        AdminsRotated(Admins, Admins, Root),
+        /// The anchor of the first root was revoked and replaced by the second.
+        AnchorSuperseded(Admins, Root, Root),
+        /// An admin leaf was disabled and may no longer act on behalf of the admin set.
+        AdminDisabled(Admins, AdminLeaf),
+        /// An anchor was revoked by `ForceOrigin`.
+        AnchorForceRevoked(Admins, Root),
+        /// A leaf was suspended by `ForceOrigin` until the given time.
+        LeafForceSuspended(Admins, Leaf, UnixTimeSeconds),
+        /// An anchor was removed from chain-state by `ForceOrigin`.
+        AnchorForceRemoved(Admins, Root),
+        /// The revocation registry of an admin set was updated to the given root.
+        RevocationRootUpdated(Admins, SparseRoot),
+        /// An admin set published a new status list.
+        StatusListUpdated(Admins, StatusListHash),
+    }
+);
+
+/// tests for this pallet
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blake2::Blake2s;
+    use frame_support::{
+        impl_outer_origin, parameter_types,
+        traits::{Contains, IntegrityTest, OnInitialize, OnRuntimeUpgrade},
+        weights::{RuntimeDbWeight, Weight},
+        IterableStorageMap,
+    };
+    use sp_core::H256;
+    use sp_runtime::{
+        testing::Header,
+        traits::{BlakeTwo256, IdentityLookup},
+        Perbill,
+    };
+    use std::cell::Cell;
+
+    // Test module shorthand
+    type Tm = Module<Test>;
+
+    impl_outer_origin! {
+        pub enum Origin for Test where system = system {}
+    }
+
+    /// Hash using Blake2s
+    fn blake(x: impl Hash) -> [u8; 32] {
+        Hashed::from_preimage::<Blake2s>(&x).hash
+    }
+
+    // For testing the pallet, we construct most of a mock runtime. This means
+    // first constructing a configuration type (`Test`) which `impl`s each of the
+    // configuration traits of modules we want to use.
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    parameter_types! {
+        pub const BlockHashCount: u64 = 250;
+        pub const MaximumBlockWeight: Weight = 1024;
+        pub const MaximumBlockLength: u32 = 2 * 1024;
+        pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+        pub const DisableThreshold: u32 = 2;
+        pub const RevealWindow: u64 = 10;
+        pub const MaxAnchorsPerAccount: u32 = 400;
+        pub const MaxAnchorsPerBlock: u32 = 300;
+        pub const QuotaWindow: u64 = 10;
+        pub const MaxCommitmentsPerBlock: u32 = 3;
+        pub const MigrationKeysPerBlock: u32 = 2;
+        pub const DbWeight: RuntimeDbWeight = RuntimeDbWeight {
+            read: 1,
+            write: 10,
+        };
+    }
+    impl system::Trait for Test {
+        type BaseCallFilter = ();
+        type Origin = Origin;
+        type Call = ();
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
         type Header = Header;
         type Event = ();
         type BlockHashCount = BlockHashCount;
         type MaximumBlockWeight = MaximumBlockWeight;
+        type DbWeight = DbWeight;
+        type BlockExecutionWeight = ();
+        type ExtrinsicBaseWeight = ();
+        type MaximumExtrinsicWeight = MaximumBlockWeight;
         type MaximumBlockLength = MaximumBlockLength;
         type AvailableBlockRatio = AvailableBlockRatio;
         type Version = ();
-        type ModuleToIndex = ();
+        type PalletInfo = ();
+        type AccountData = ();
+        type OnNewAccount = ();
+        type OnKilledAccount = ();
+        type SystemWeightInfo = ();
     }
-    impl Trait for Test {
-        type Event = ();
-        type TreeHash = Blake2s;
-        type TreeHashOut = [u8; 32];
-        fn hash_account_id(account_id: &u64, hasher: &mut Blake2s) {
-            hasher.input(account_id.to_be_bytes())
-        }
+    thread_local! {
+        static NOW: Cell<UnixTimeSeconds> = Cell::new(0);
+        static ANCHOR_POLICY: Cell<AnchorPolicy> = Cell::new(AnchorPolicy::AnySigned);
+    }
+
+    /// The ways a runtime may restrict anchoring, selected per test with `set_anchor_policy`.
+    #[derive(Clone, Copy)]
+    enum AnchorPolicy {
+        /// Any signed account may anchor.
+        AnySigned,
+        /// Only accounts in `Issuers` may anchor, as with a whitelist pallet.
+        Whitelist,
+        /// Only a collective may anchor. The mock has no collective pallet, root stands in for
+        /// the collective's origin.
+        Collective,
+    }
+
+    fn set_anchor_policy(policy: AnchorPolicy) {
+        ANCHOR_POLICY.with(|p| p.set(policy));
+    }
+
+    /// Registered issuers.
+    pub struct Issuers;
+    impl Contains<u64> for Issuers {
+        fn sorted_members() -> Vec<u64> {
+            vec![0, 1]
+        }
+    }
+
+    pub struct MockAnchorOrigin;
+    impl EnsureOrigin<Origin> for MockAnchorOrigin {
+        type Success = ();
+        fn try_origin(o: Origin) -> Result<(), Origin> {
+            match ANCHOR_POLICY.with(|p| p.get()) {
+                AnchorPolicy::AnySigned => system::EnsureSigned::<u64>::try_origin(o).map(|_| ()),
+                AnchorPolicy::Whitelist => {
+                    system::EnsureSignedBy::<Issuers, u64>::try_origin(o).map(|_| ())
+                }
+                AnchorPolicy::Collective => system::EnsureRoot::<u64>::try_origin(o),
+            }
+        }
+    }
+
+    /// Set the time reported by `Test::current_time()`.
+    fn set_time(now: UnixTimeSeconds) {
+        NOW.with(|n| n.set(now));
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type TreeHash = Blake2s;
+        type TreeHashOut = [u8; 32];
+        type DisableThreshold = DisableThreshold;
+        type RevealWindow = RevealWindow;
+        type MaxAnchorsPerAccount = MaxAnchorsPerAccount;
+        type MaxAnchorsPerBlock = MaxAnchorsPerBlock;
+        type QuotaWindow = QuotaWindow;
+        type MaxCommitmentsPerBlock = MaxCommitmentsPerBlock;
+        type MigrationKeysPerBlock = MigrationKeysPerBlock;
+        type AnchorOrigin = MockAnchorOrigin;
+        type ForceOrigin = system::EnsureRoot<u64>;
+        fn current_time() -> UnixTimeSeconds {
+            NOW.with(|n| n.get())
+        }
+        fn hash_account_id(account_id: &u64, hasher: &mut Blake2s) {
+            hasher.input(account_id.to_be_bytes())
+        }
+    }
+    /// Build a merkle root over already hashed leaves, along with a proof of inclusion for each
+    /// leaf. The number of leaves must be a power of two.
+    fn merkle_tree<T>(
+        leaves: &[[u8; 32]],
+    ) -> (MerkleRoot<T, [u8; 32]>, Vec<Vec<ProofElement<[u8; 32]>>>) {
+        assert!(leaves.len().is_power_of_two());
+        let mut level: Vec<[u8; 32]> = leaves.iter().map(blake).collect();
+        let mut proofs = vec![vec![]; leaves.len()];
+        let mut width = 1;
+        while level.len() > 1 {
+            for (i, proof) in proofs.iter_mut().enumerate() {
+                let node = i / width;
+                proof.push(if node % 2 == 0 {
+                    ProofElement::Right(level[node + 1])
+                } else {
+                    ProofElement::Left(level[node - 1])
+                });
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| blake((pair[0], pair[1])))
+                .collect();
+            width *= 2;
+        }
+        (MerkleRoot::from_root(level[0]), proofs)
+    }
+
+    /// The admin leaf of an account in the mock runtime.
+    fn account_leaf(account: u64) -> [u8; 32] {
+        blake(&account.to_be_bytes()[..])
+    }
+
+    /// The admin leaf of an account scoped to `role` in the mock runtime.
+    fn role_leaf(account: u64, role: Role) -> [u8; 32] {
+        blake((&account.to_be_bytes()[..], role as u8))
+    }
+
+    /// The admin leaf of an account limited to a validity period in the mock runtime.
+    fn validity_leaf(account: u64, role: Option<Role>, validity: Validity) -> [u8; 32] {
+        match role {
+            None => blake((&account.to_be_bytes()[..], validity)),
+            Some(role) => blake((&account.to_be_bytes()[..], role as u8, validity)),
+        }
+    }
+
+    // This function basically just builds a genesis storage key/value store according to
+    // our desired mockup.
+    fn new_test_ext() -> sp_io::TestExternalities {
+        system::GenesisConfig::default()
+            .build_storage::<Test>()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn default_values_are_none() {
+        new_test_ext().execute_with(|| {
+            let auth = MerkleRoot::from_root([0u8; 32]);
+            let docs = MerkleRoot::from_root([0u8; 32]);
+            assert!(!Anchors::<Test>::contains_key(&auth, &docs));
+            assert_eq!(Anchors::<Test>::get(&auth, &docs), None);
+
+            let auth = MerkleRoot::from_root([0u8; 32]);
+            let doc = Hashed::prehashed([0u8; 32]);
+            assert!(!SuspendedLeaves::<Test>::contains_key(&auth, &doc));
+            assert_eq!(SuspendedLeaves::<Test>::get(&auth, &doc), None);
+        });
+    }
+
+    #[test]
+    fn anchor() {
+        new_test_ext().execute_with(|| {
+            let (auths, root) = Default::default();
+            assert_eq!(Tm::lookup_anchor(&auths, &root, 0), None);
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None)
+                .expect_err("Was able to anchor twice.");
+            Tm::create_anchor(Origin::signed(1), auths.clone(), root.clone(), None)
+                .expect_err("Was able to anchor twice.");
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .expect_err("Since auths represents the empty set, the root should be irrevocable.");
+        });
+    }
+
+    #[test]
+    fn revoke_anchor() {
+        // a merkle root representing { 0u64 }
+        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let docs = Default::default();
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(Tm::lookup_anchor(&auths, &docs, 0), None);
+            Tm::create_anchor(Origin::signed(0), auths.clone(), docs.clone(), None).unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &docs, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                docs.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &docs, 0),
+                Some(AnchorStatus::Revoked)
+            );
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                docs.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            Tm::create_anchor(Origin::signed(0), auths.clone(), docs.clone(), None).unwrap_err();
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                docs.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                docs.clone(),
+                vec![].into(),
+            )
+            .unwrap_err();
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                MerkleRoot::from_root([0; 32]),
+                vec![].into(),
+            )
+            .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                MerkleRoot::from_root([1; 32]),
+                vec![].into(),
+            )
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn suspend_leaf() {
+        // a merkle root representing { 0u64 }
+        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let doc: Hashed<Document, [u8; 32]> = Default::default();
+
+        let ua = Origin::signed(0);
+        let ub = Origin::signed(1);
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), false);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), false);
+            Tm::suspend_leaf(ua.clone(), vec![].into(), auths.clone(), doc.clone(), 10).unwrap();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), true);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), false);
+            Tm::suspend_leaf(ua.clone(), vec![].into(), auths.clone(), doc.clone(), 11).unwrap();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), true);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), true);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 12), false);
+
+            // Revoke
+            Tm::suspend_leaf(
+                ua.clone(),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                u64::max_value(),
+            )
+            .unwrap();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 12), true);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, u64::max_value()), true);
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::suspend_leaf(ua.clone(), vec![].into(), auths.clone(), doc.clone(), 0).unwrap();
+            Tm::suspend_leaf(ua.clone(), vec![].into(), auths.clone(), doc.clone(), 0).unwrap_err();
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::suspend_leaf(ub.clone(), vec![].into(), auths.clone(), doc.clone(), 0).unwrap_err();
+        });
+    }
+
+    #[test]
+    fn create_anchors() {
+        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let b = MerkleRoot::from_root([2u8; 32]);
+        let c = MerkleRoot::from_root([3u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![]).unwrap();
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![a.clone(), b.clone()])
+                .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+
+            // b is already anchored so the whole batch is rejected
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![c.clone(), b.clone()])
+                .unwrap_err();
+            assert_eq!(Tm::lookup_anchor(&auths, &c, 0), None);
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![a.clone(), a.clone()])
+                .unwrap_err();
+            assert_eq!(Tm::lookup_anchor(&auths, &a, 0), None);
+
+            let too_many = (0..=MAX_BATCH_SIZE as u32)
+                .map(|i| MerkleRoot::from_root(blake(&i.to_be_bytes()[..])))
+                .collect();
+            Tm::create_anchors(Origin::signed(0), auths.clone(), too_many).unwrap_err();
+        });
+    }
+
+    #[test]
+    fn suspend_leaves() {
+        // a merkle root representing { 0u64 }
+        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let a: Hashed<Document, [u8; 32]> = Hashed::prehashed([1u8; 32]);
+        let b: Hashed<Document, [u8; 32]> = Hashed::prehashed([2u8; 32]);
+
+        let ua = Origin::signed(0);
+        let ub = Origin::signed(1);
+
+        new_test_ext().execute_with(|| {
+            Tm::suspend_leaves(
+                ua.clone(),
+                vec![].into(),
+                auths.clone(),
+                vec![(a.clone(), 10), (b.clone(), 20)],
+            )
+            .unwrap();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &a, 10), true);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &a, 11), false);
+            assert_eq!(Tm::leaf_suspended_by(&auths, &b, 20), true);
+
+            // a may not be shortened so the whole batch is rejected
+            Tm::suspend_leaves(
+                ua.clone(),
+                vec![].into(),
+                auths.clone(),
+                vec![(b.clone(), 30), (a.clone(), 5)],
+            )
+            .unwrap_err();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &b, 30), false);
+        });
+
+        new_test_ext().execute_with(|| {
+            Tm::suspend_leaves(
+                ub.clone(),
+                vec![].into(),
+                auths.clone(),
+                vec![(a.clone(), 10)],
+            )
+            .unwrap_err();
+            Tm::suspend_leaves(
+                ua.clone(),
+                vec![].into(),
+                auths.clone(),
+                vec![(a.clone(), 10), (a.clone(), 20)],
+            )
+            .unwrap_err();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &a, 0), false);
+        });
+    }
+
+    #[test]
+    fn rotate_admins() {
+        // merkle roots representing { 0u64 } and { 1u64 }
+        let auths0 = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let auths1 = MerkleRoot::from_root(blake(blake(&1u64.to_be_bytes()[..])));
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([2u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap();
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                vec![].into(),
+                auths0.clone(),
+                leaf.clone(),
+                10,
+            )
+            .unwrap();
+
+            // only members of the current admins may rotate
+            Tm::rotate_admins(
+                Origin::signed(1),
+                auths0.clone(),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![],
+            )
+            .unwrap_err();
+            Tm::rotate_admins(
+                Origin::signed(0),
+                auths0.clone(),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![leaf.clone()],
+            )
+            .unwrap();
+
+            assert_eq!(Tm::current_admins(&auths0, &root), auths1);
+            assert_eq!(
+                Tm::lookup_anchor(&auths0, &root, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths1, &root, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(Tm::leaf_suspended_by(&auths0, &leaf, 10), false);
+            assert_eq!(Tm::leaf_suspended_by(&auths1, &leaf, 10), true);
+
+            // the old admins lost their authority over the anchor
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths0.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap_err();
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap_err();
+            // rotating back would create a cycle
+            Tm::rotate_admins(
+                Origin::signed(1),
+                auths1.clone(),
+                auths0.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![],
+            )
+            .unwrap_err();
+
+            Tm::revoke_anchor(
+                Origin::signed(1),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths0, &root, 0),
+                Some(AnchorStatus::Revoked)
+            );
+        });
+
+        new_test_ext().execute_with(|| {
+            // unanchored roots can not be rotated
+            Tm::rotate_admins(
+                Origin::signed(0),
+                auths0.clone(),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![],
+            )
+            .unwrap_err();
+            // neither can leaves that are not suspended
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap();
+            Tm::rotate_admins(
+                Origin::signed(0),
+                auths0.clone(),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![leaf.clone()],
+            )
+            .unwrap_err();
+            assert_eq!(Tm::current_admins(&auths0, &root), auths0);
+        });
+    }
+
+    #[test]
+    fn disable_admin() {
+        let leaves: Vec<[u8; 32]> = (0..4).map(account_leaf).collect();
+        let (auths, proofs) = merkle_tree(&leaves);
+        let target = Hashed::prehashed(leaves[3]);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let doc: Hashed<Document, [u8; 32]> = Default::default();
+
+        new_test_ext().execute_with(|| {
+            // non-members may not vote
+            Tm::disable_admin(
+                Origin::signed(4),
+                auths.clone(),
+                proofs[0].clone().into(),
+                target.clone(),
+            )
+            .unwrap_err();
+
+            Tm::disable_admin(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                target.clone(),
+            )
+            .unwrap();
+            Tm::disable_admin(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                target.clone(),
+            )
+            .expect_err("Was able to vote twice.");
+            // below the threshold, the target is still an admin
+            Tm::suspend_leaf(
+                Origin::signed(3),
+                proofs[3].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                1,
+            )
+            .unwrap();
+
+            Tm::disable_admin(
+                Origin::signed(1),
+                auths.clone(),
+                proofs[1].clone().into(),
+                target.clone(),
+            )
+            .unwrap();
+            assert!(DisabledAdmins::<Test>::get((&auths, &target)));
+            Tm::disable_admin(
+                Origin::signed(2),
+                auths.clone(),
+                proofs[2].clone().into(),
+                target.clone(),
+            )
+            .expect_err("Was able to disable twice.");
+
+            let refused = Tm::suspend_leaf(
+                Origin::signed(3),
+                proofs[3].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                2,
+            )
+            .unwrap_err();
+            assert_eq!(
+                refused.error,
+                DispatchError::from(Error::<Test>::AdminDisabled)
+            );
+            Tm::revoke_anchor(
+                Origin::signed(3),
+                auths.clone(),
+                root.clone(),
+                proofs[3].clone().into(),
+            )
+            .unwrap_err();
+            Tm::disable_admin(
+                Origin::signed(3),
+                auths.clone(),
+                proofs[3].clone().into(),
+                Hashed::prehashed(leaves[0]),
+            )
+            .unwrap_err();
+
+            // the remaining admins are unaffected
+            Tm::revoke_anchor(
+                Origin::signed(2),
+                auths.clone(),
+                root.clone(),
+                proofs[2].clone().into(),
+            )
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn role_scoped_admins() {
+        let leaves = [
+            account_leaf(0),
+            role_leaf(1, Role::Suspender),
+            role_leaf(2, Role::Revoker),
+            role_leaf(3, Role::Reinstater),
+        ];
+        let (auths, proofs) = merkle_tree(&leaves);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let doc: Hashed<Document, [u8; 32]> = Default::default();
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
+
+            // suspenders may only suspend
+            Tm::revoke_anchor(
+                Origin::signed(1),
+                auths.clone(),
+                root.clone(),
+                proofs[1].clone().into(),
+            )
+            .unwrap_err();
+            Tm::suspend_leaf(
+                Origin::signed(1),
+                proofs[1].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                10,
+            )
+            .unwrap();
+            Tm::reinstate_leaf(
+                Origin::signed(1),
+                proofs[1].clone().into(),
+                auths.clone(),
+                doc.clone(),
+            )
+            .unwrap_err();
+
+            // reinstaters may only reinstate
+            Tm::suspend_leaf(
+                Origin::signed(3),
+                proofs[3].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                20,
+            )
+            .unwrap_err();
+            Tm::reinstate_leaf(
+                Origin::signed(3),
+                proofs[3].clone().into(),
+                auths.clone(),
+                doc.clone(),
+            )
+            .unwrap();
+            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 0), false);
+            Tm::reinstate_leaf(
+                Origin::signed(3),
+                proofs[3].clone().into(),
+                auths.clone(),
+                doc.clone(),
+            )
+            .expect_err("Was able to reinstate a leaf that is not suspended.");
+
+            // revokers may only revoke
+            Tm::suspend_leaf(
+                Origin::signed(2),
+                proofs[2].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                20,
+            )
+            .unwrap_err();
+            Tm::revoke_anchor(
+                Origin::signed(2),
+                auths.clone(),
+                root.clone(),
+                proofs[2].clone().into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Revoked)
+            );
+
+            // unscoped admins may do anything, but permanent revocations are final
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proofs[0].clone().into(),
+                auths.clone(),
+                doc.clone(),
+                u64::max_value(),
+            )
+            .unwrap();
+            Tm::reinstate_leaf(
+                Origin::signed(0),
+                proofs[0].clone().into(),
+                auths.clone(),
+                doc.clone(),
+            )
+            .unwrap_err();
+
+            // role scoped admins may not perform actions reserved to unscoped admins
+            Tm::disable_admin(
+                Origin::signed(2),
+                auths.clone(),
+                proofs[2].clone().into(),
+                Hashed::prehashed(leaves[1]),
+            )
+            .unwrap_err();
+        });
+    }
+
+    #[test]
+    fn delegate() {
+        let leaves = [account_leaf(0), role_leaf(1, Role::Suspender)];
+        let (auths, proofs) = merkle_tree(&leaves);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let doc: Hashed<Document, [u8; 32]> = Default::default();
+
+        new_test_ext().execute_with(|| {
+            set_time(50);
+            // delegates may only be appointed by admins holding the delegated role
+            Tm::delegate(
+                Origin::signed(1),
+                auths.clone(),
+                proofs[1].clone().into(),
+                5,
+                Role::Revoker,
+                100,
+            )
+            .unwrap_err();
+            Tm::delegate(
+                Origin::signed(1),
+                auths.clone(),
+                proofs[1].clone().into(),
+                5,
+                Role::Suspender,
+                100,
+            )
+            .unwrap();
+            // other admins may neither replace nor end the delegation
+            for expires in &[200, 0] {
+                assert_eq!(
+                    Tm::delegate(
+                        Origin::signed(0),
+                        auths.clone(),
+                        proofs[0].clone().into(),
+                        5,
+                        Role::Suspender,
+                        *expires,
+                    ),
+                    Err(Error::<Test>::DelegationNotOwned.into())
+                );
+            }
+
+            Tm::suspend_leaf(
+                Origin::signed(5),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                10,
+            )
+            .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(5),
+                auths.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap_err();
+            Tm::suspend_leaf(
+                Origin::signed(6),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                11,
+            )
+            .unwrap_err();
+            // delegates may not delegate further
+            Tm::delegate(
+                Origin::signed(5),
+                auths.clone(),
+                vec![].into(),
+                6,
+                Role::Suspender,
+                100,
+            )
+            .unwrap_err();
+
+            set_time(100);
+            Tm::suspend_leaf(
+                Origin::signed(5),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                11,
+            )
+            .unwrap();
+            set_time(101);
+            Tm::suspend_leaf(
+                Origin::signed(5),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                12,
+            )
+            .expect_err("Delegation should have expired.");
+            // once expired, any admin holding the role may delegate it again
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                5,
+                Role::Suspender,
+                150,
+            )
+            .unwrap();
+
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                5,
+                Role::Revoker,
+                200,
+            )
+            .unwrap();
+            // an expiry in the past ends the delegation
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                5,
+                Role::Revoker,
+                0,
+            )
+            .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(5),
+                auths.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap_err();
+        });
+    }
+
+    #[test]
+    fn admin_validity() {
+        let validity = Validity {
+            not_before: 10,
+            not_after: 20,
+        };
+        let leaves = [
+            validity_leaf(0, None, validity),
+            validity_leaf(1, Some(Role::Revoker), validity),
+        ];
+        let (auths, proofs) = merkle_tree(&leaves);
+        let proof = |i: usize, validity: Option<Validity>| AdminProof {
+            validity,
+            path: proofs[i].clone().into(),
+        };
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let doc: Hashed<Document, [u8; 32]> = Default::default();
+
+        new_test_ext().execute_with(|| {
+            set_time(9);
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, Some(validity)),
+                auths.clone(),
+                doc.clone(),
+                1,
+            )
+            .expect_err("Leaf should not be valid yet.");
+            set_time(10);
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, Some(validity)),
+                auths.clone(),
+                doc.clone(),
+                1,
+            )
+            .unwrap();
+            // the validity period is part of the leaf
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, None),
+                auths.clone(),
+                doc.clone(),
+                2,
+            )
+            .unwrap_err();
+            let other = Validity {
+                not_before: 0,
+                not_after: 100,
+            };
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, Some(other)),
+                auths.clone(),
+                doc.clone(),
+                2,
+            )
+            .unwrap_err();
+            set_time(20);
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, Some(validity)),
+                auths.clone(),
+                doc.clone(),
+                2,
+            )
+            .unwrap();
+
+            // validity periods combine with roles
+            Tm::suspend_leaf(
+                Origin::signed(1),
+                proof(1, Some(validity)),
+                auths.clone(),
+                doc.clone(),
+                3,
+            )
+            .unwrap_err();
+            Tm::revoke_anchor(
+                Origin::signed(1),
+                auths.clone(),
+                root.clone(),
+                proof(1, Some(validity)),
+            )
+            .unwrap();
+
+            // delegations end with the validity period of the granting leaf at the latest
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proof(0, Some(validity)),
+                5,
+                Role::Suspender,
+                21,
+            )
+            .expect_err("Delegated beyond the validity period.");
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proof(0, Some(validity)),
+                5,
+                Role::Suspender,
+                20,
+            )
+            .unwrap();
+
+            set_time(21);
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                proof(0, Some(validity)),
+                auths.clone(),
+                doc.clone(),
+                3,
+            )
+            .expect_err("Leaf should have expired.");
+            Tm::suspend_leaf(
+                Origin::signed(5),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                3,
+            )
+            .expect_err("Delegation should have ended with the leaf.");
+        });
+    }
+
+    #[test]
+    fn commit_reveal_anchor() {
+        let auths = MerkleRoot::from_root([1u8; 32]);
+        let root = MerkleRoot::from_root([2u8; 32]);
+        let salt = [3u8; 32];
+        let commitment = Hashed::prehashed(blake((&auths, &root, &salt)));
+
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            // a commitment copied by another account does not block the original
+            Tm::commit_anchor(Origin::signed(1), commitment.clone()).unwrap();
+            Tm::commit_anchor(Origin::signed(0), commitment.clone()).unwrap();
+            Tm::commit_anchor(Origin::signed(0), commitment.clone())
+                .expect_err("Was able to commit twice.");
+            assert_eq!(Tm::lookup_anchor(&auths, &root, 0), None);
+
+            Tm::reveal_anchor(Origin::signed(2), auths.clone(), root.clone(), salt)
+                .expect_err("Only a committer may reveal.");
+            Tm::reveal_anchor(Origin::signed(0), auths.clone(), root.clone(), [4u8; 32])
+                .expect_err("Was able to reveal with the wrong salt.");
+
+            system::Module::<Test>::set_block_number(11);
+            Tm::reveal_anchor(Origin::signed(0), auths.clone(), root.clone(), salt).unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Valid(11))
+            );
+            Tm::reveal_anchor(Origin::signed(0), auths.clone(), root.clone(), salt)
+                .expect_err("Was able to reveal twice.");
+        });
+
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            Tm::commit_anchor(Origin::signed(0), commitment.clone()).unwrap();
+            Tm::on_initialize(12);
+            system::Module::<Test>::set_block_number(12);
+            Tm::reveal_anchor(Origin::signed(0), auths.clone(), root.clone(), salt)
+                .expect_err("Commitment should have been removed.");
+            assert_eq!(Tm::lookup_anchor(&auths, &root, 0), None);
+        });
+
+        new_test_ext().execute_with(|| {
+            // a commitment made again after the first one was revealed outlives the deadline of
+            // the first
+            let other = MerkleRoot::from_root([5u8; 32]);
+            let other_commitment = Hashed::prehashed(blake((&other, &root, &salt)));
+            system::Module::<Test>::set_block_number(1);
+            Tm::commit_anchor(Origin::signed(0), other_commitment.clone()).unwrap();
+            Tm::reveal_anchor(Origin::signed(0), other.clone(), root.clone(), salt).unwrap();
+            Tm::commit_anchor(Origin::signed(0), commitment.clone()).unwrap();
+            system::Module::<Test>::set_block_number(2);
+            Tm::commit_anchor(Origin::signed(0), other_commitment.clone()).unwrap();
+            Tm::on_initialize(12);
+            assert!(AnchorCommitments::<Test>::contains_key(
+                0,
+                &other_commitment
+            ));
+            assert!(!AnchorCommitments::<Test>::contains_key(0, &commitment));
+
+            // revealing an anchor that already exists fails
+            system::Module::<Test>::set_block_number(12);
+            Tm::reveal_anchor(Origin::signed(0), other.clone(), root.clone(), salt).unwrap_err();
+        });
+
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            for account in 0..3 {
+                Tm::commit_anchor(Origin::signed(account), commitment.clone()).unwrap();
+            }
+            assert_eq!(
+                Tm::commit_anchor(Origin::signed(3), commitment.clone()),
+                Err(Error::<Test>::CommitmentQuotaExceeded.into())
+            );
+            system::Module::<Test>::set_block_number(2);
+            Tm::commit_anchor(Origin::signed(3), commitment.clone()).unwrap();
+
+            // removing expired commitments is weighed by the number of commitments, besides
+            // reading the migration cursor and taking the idle accounts of the block
+            assert_eq!(Tm::on_initialize(12), 6 + 5 * 10);
+            assert_eq!(Tm::on_initialize(13), 4 + 3 * 10);
+            assert_eq!(Tm::on_initialize(14), 3 + 2 * 10);
+            assert!((0..4).all(|a| !AnchorCommitments::<Test>::contains_key(a, &commitment)));
+        });
+    }
+
+    #[test]
+    fn supersede_anchor() {
+        let leaves = [account_leaf(0), role_leaf(1, Role::Suspender)];
+        let (auths, proofs) = merkle_tree(&leaves);
+        let auths1 = MerkleRoot::from_root(blake(account_leaf(2)));
+        let v1 = MerkleRoot::from_root([1u8; 32]);
+        let v2 = MerkleRoot::from_root([2u8; 32]);
+        let v3 = MerkleRoot::from_root([3u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            Tm::supersede_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                v1.clone(),
+                v2.clone(),
+                proofs[0].clone().into(),
+            )
+            .expect_err("Was able to supersede an anchor that does not exist.");
+
+            Tm::create_anchor(Origin::signed(0), auths.clone(), v1.clone(), None).unwrap();
+            Tm::supersede_anchor(
+                Origin::signed(1),
+                auths.clone(),
+                v1.clone(),
+                v2.clone(),
+                proofs[1].clone().into(),
+            )
+            .expect_err("Only revokers may supersede.");
+            Tm::supersede_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                v1.clone(),
+                v1.clone(),
+                proofs[0].clone().into(),
+            )
+            .expect_err("An anchor may not supersede itself.");
+            Tm::supersede_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                v1.clone(),
+                v2.clone(),
+                proofs[0].clone().into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &v1, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &v2, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(Tm::current_version(&auths, &v1), v2);
+            assert_eq!(Tm::current_version(&auths, &v2), v2);
+            Tm::supersede_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                v1.clone(),
+                v3.clone(),
+                proofs[0].clone().into(),
+            )
+            .expect_err("Was able to supersede twice.");
+
+            // supersessions are followed across rotations
+            Tm::rotate_admins(
+                Origin::signed(0),
+                auths.clone(),
+                auths1.clone(),
+                v2.clone(),
+                proofs[0].clone().into(),
+                vec![],
+            )
+            .unwrap();
+            Tm::supersede_anchor(
+                Origin::signed(2),
+                auths1.clone(),
+                v2.clone(),
+                v3.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v3);
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &v1, 0),
+                Some(AnchorStatus::Revoked)
+            );
+        });
+    }
+
+    #[test]
+    fn anchor_validity() {
+        let auths = MerkleRoot::from_root(blake(account_leaf(0)));
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let validity = Validity {
+            not_before: 10,
+            not_after: 20,
+        };
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                Some(Validity {
+                    not_before: 20,
+                    not_after: 10,
+                }),
+            )
+            .unwrap_err();
+            Tm::create_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                Some(validity),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 9),
+                Some(AnchorStatus::NotYetValid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 10),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 20),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 21),
+                Some(AnchorStatus::Expired(1))
+            );
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 10),
+                Some(AnchorStatus::Revoked)
+            );
+        });
+    }
+
+    #[test]
+    fn verify_document() {
+        let auths = MerkleRoot::from_root(blake(account_leaf(0)));
+        let docs = [
+            blake(&b"{\"document\": 1}"[..]),
+            blake(&b"{\"document\": 2}"[..]),
+        ];
+        let (root, proofs) = merkle_tree(&docs);
+        let doc = Hashed::prehashed(docs[0]);
+        let status = |now| Tm::verify_document(&auths, &root, &proofs[0], &doc, now);
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(status(10), DocumentStatus::NotAnchored);
+            Tm::create_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                Some(Validity {
+                    not_before: 10,
+                    not_after: 20,
+                }),
+            )
+            .unwrap();
+            assert_eq!(status(9), DocumentStatus::NotYetValid);
+            assert_eq!(status(10), DocumentStatus::Valid);
+            assert_eq!(status(21), DocumentStatus::Expired);
+            assert_eq!(
+                Tm::verify_document(&auths, &root, &proofs[1], &doc, 10),
+                DocumentStatus::NotIncluded
+            );
+
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                vec![].into(),
+                auths.clone(),
+                doc.clone(),
+                15,
+            )
+            .unwrap();
+            assert_eq!(status(15), DocumentStatus::Suspended);
+            assert_eq!(status(16), DocumentStatus::Valid);
+
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(status(16), DocumentStatus::Revoked);
+        });
+    }
+
+    #[test]
+    fn anchors_for_root() {
+        let auths0 = MerkleRoot::from_root(blake(account_leaf(0)));
+        let auths1 = MerkleRoot::from_root(blake(account_leaf(1)));
+        let auths2 = MerkleRoot::from_root(blake(account_leaf(2)));
+        let root = MerkleRoot::from_root([1u8; 32]);
+        // admin sets are listed in storage order
+        let sorted = |mut all: Vec<MerkleRoot<u64, [u8; 32]>>| {
+            all.sort_by_key(|admins| admins.encode());
+            all
+        };
+
+        new_test_ext().execute_with(|| {
+            assert!(Tm::anchors_for_root(&root).is_empty());
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap();
+            Tm::create_anchors(Origin::signed(0), auths1.clone(), vec![root.clone()]).unwrap();
+            assert_eq!(
+                sorted(Tm::anchors_for_root(&root)),
+                sorted(vec![auths0.clone(), auths1.clone()])
+            );
+
+            // revoked anchors stay in the index, also when they were never created
+            Tm::revoke_anchor(
+                Origin::signed(1),
+                auths1.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(2),
+                auths2.clone(),
+                root.clone(),
+                vec![].into(),
+            )
+            .unwrap();
+            assert_eq!(
+                sorted(Tm::anchors_for_root(&root)),
+                sorted(vec![auths0.clone(), auths1.clone(), auths2.clone()])
+            );
+
+            // rotations replace the old admin set
+            let auths3 = MerkleRoot::from_root(blake(account_leaf(3)));
+            Tm::rotate_admins(
+                Origin::signed(0),
+                auths0.clone(),
+                auths3.clone(),
+                root.clone(),
+                vec![].into(),
+                vec![],
+            )
+            .unwrap();
+            assert_eq!(
+                sorted(Tm::anchors_for_root(&root)),
+                sorted(vec![auths1.clone(), auths2.clone(), auths3.clone()])
+            );
+        });
+    }
+
+    #[test]
+    fn enumerate_by_admins() {
+        let auths0 = MerkleRoot::from_root(blake(account_leaf(0)));
+        let auths1 = MerkleRoot::from_root(blake(account_leaf(1)));
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let b = MerkleRoot::from_root([2u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([3u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchors(
+                Origin::signed(0),
+                auths0.clone(),
+                vec![a.clone(), b.clone()],
+            )
+            .unwrap();
+            Tm::create_anchor(Origin::signed(0), auths1.clone(), a.clone(), None).unwrap();
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                vec![].into(),
+                auths0.clone(),
+                leaf.clone(),
+                5,
+            )
+            .unwrap();
+
+            let mut anchors = Tm::anchors_by_admins(&auths0);
+            anchors.sort_by_key(|(root, _)| root.encode());
+            assert_eq!(
+                anchors,
+                vec![
+                    (a.clone(), Revokable::NotRevoked(1)),
+                    (b.clone(), Revokable::NotRevoked(1))
+                ]
+            );
+            assert_eq!(
+                Tm::anchors_by_admins(&auths1),
+                vec![(a.clone(), Revokable::NotRevoked(1))]
+            );
+            assert_eq!(Tm::suspensions_by_admins(&auths0), vec![(leaf.clone(), 5)]);
+            assert!(Tm::suspensions_by_admins(&auths1).is_empty());
+        });
+    }
+
+    #[test]
+    fn migrate_legacy_storage() {
+        let auths = MerkleRoot::from_root(blake(account_leaf(0)));
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let b = MerkleRoot::from_root([2u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([3u8; 32]);
+        let other: Hashed<Document, [u8; 32]> = Hashed::prehashed([4u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            migration::deprecated::Anchors::<Test>::insert((&auths, &a), Revokable::NotRevoked(1));
+            migration::deprecated::Anchors::<Test>::insert((&auths, &b), Revokable::Revoked);
+            migration::deprecated::SuspendedLeaves::<Test>::insert((&auths, &leaf), 10);
+            migration::deprecated::SuspendedLeaves::<Test>::insert((&auths, &other), 10);
+            Tm::on_runtime_upgrade();
+
+            // legacy entries count as anchored
+            Tm::create_anchor(Origin::signed(0), auths.clone(), a.clone(), None)
+                .expect_err("The root has already been anchored.");
+            // written to the current layout before the legacy entry was migrated
+            Anchors::<Test>::insert(&auths, &b, Revokable::NotRevoked(2));
+            // claims the legacy entry before extending it
+            Tm::suspend_leaf(
+                Origin::signed(0),
+                vec![].into(),
+                auths.clone(),
+                leaf.clone(),
+                20,
+            )
+            .unwrap();
+
+            let anchors = vec![(auths.clone(), a.clone()), (auths.clone(), b.clone())];
+            let suspensions = vec![
+                (auths.clone(), leaf.clone()),
+                (auths.clone(), other.clone()),
+            ];
+            Tm::migrate_legacy_storage(Origin::signed(0), anchors.clone(), suspensions.clone())
+                .expect_err("Only root may migrate.");
+            Tm::migrate_legacy_storage(system::RawOrigin::Root.into(), anchors, suspensions)
+                .unwrap();
+
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            // a revocation in either layout wins
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            // the later suspension end wins
+            assert_eq!(SuspendedLeaves::<Test>::get(&auths, &leaf), Some(20));
+            assert_eq!(SuspendedLeaves::<Test>::get(&auths, &other), Some(10));
+            assert!(Tm::anchors_for_root(&a).contains(&auths));
+
+            assert_eq!(LegacyAnchors::<Test>::iter().count(), 0);
+            assert_eq!(LegacySuspendedLeaves::iter().count(), 0);
+        });
+    }
+
+    #[test]
+    fn clear_legacy_entries() {
+        let auths = MerkleRoot::from_root(blake(account_leaf(0)));
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([3u8; 32]);
+        // keys whose preimage is known only to the chain that wrote them
+        let anchor_hash = sp_io::hashing::blake2_256(&(&auths, &a).encode());
+        let leaf_hash = sp_io::hashing::blake2_256(&(&auths, &leaf).encode());
+
+        new_test_ext().execute_with(|| {
+            migration::deprecated::Anchors::<Test>::insert((&auths, &a), Revokable::NotRevoked(1));
+            migration::deprecated::SuspendedLeaves::<Test>::insert((&auths, &leaf), 10);
+            // the anchor is moved on upgrade, the suspension would be in the next block
+            Tm::on_runtime_upgrade();
+            assert!(snapshot::export::<Test>().is_err());
+
+            Tm::clear_legacy_entries(Origin::signed(0), vec![anchor_hash], vec![leaf_hash])
+                .expect_err("Only root may clear legacy entries.");
+            assert_eq!(
+                Tm::clear_legacy_entries(
+                    system::RawOrigin::Root.into(),
+                    vec![[0u8; 32]; MAX_BATCH_SIZE],
+                    vec![leaf_hash]
+                ),
+                Err(Error::<Test>::BatchTooLarge.into())
+            );
+            Tm::clear_legacy_entries(
+                system::RawOrigin::Root.into(),
+                vec![anchor_hash],
+                vec![leaf_hash],
+            )
+            .unwrap();
+
+            assert_eq!(Tm::lookup_anchor(&auths, &a, 0), None);
+            assert!(!Tm::leaf_suspended_by(&auths, &leaf, 10));
+            Tm::on_initialize(2);
+            assert_eq!(LegacySuspendedLeaves::iter().count(), 0);
+            assert!(snapshot::export::<Test>().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn runtime_upgrade() {
+        let auths = MerkleRoot::from_root(blake(account_leaf(0)));
+        let (a, b, c) = (
+            MerkleRoot::from_root([1u8; 32]),
+            MerkleRoot::from_root([2u8; 32]),
+            MerkleRoot::from_root([3u8; 32]),
+        );
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([4u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            // storage written before versioning was introduced
+            assert_eq!(StorageVersion::get(), Releases::V1_0_0);
+            migration::deprecated::Anchors::<Test>::insert((&auths, &a), Revokable::NotRevoked(1));
+            migration::deprecated::Anchors::<Test>::insert((&auths, &b), Revokable::Revoked);
+            migration::deprecated::SuspendedLeaves::<Test>::insert((&auths, &leaf), 10);
+            // entries in the current layout are left alone
+            Tm::create_anchor(Origin::signed(0), auths.clone(), c.clone(), None).unwrap();
+
+            // 6 keys to visit, 2 per block
+            let upgrade = Tm::on_runtime_upgrade();
+            assert_eq!(StorageVersion::get(), Releases::CURRENT);
+            assert!(ParkingCursor::exists());
+            // entries are looked up where they are while the migration is in progress
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            assert!(Tm::leaf_suspended_by(&auths, &leaf, 10));
+            let weight = upgrade + Tm::on_initialize(2) + Tm::on_initialize(3);
+            assert!(!ParkingCursor::exists());
+            // 3 entries moved in 3 blocks: 1 + 3 + 6 + 3 reads of weight 1 and 2 + 3 + 2 * 3
+            // writes of weight 10, besides removing the expired commitments and idle accounts of
+            // 2 blocks
+            assert_eq!(weight, 13 + 11 * 10 + 2 * 22);
+            // once done, only the cursor is read
+            assert_eq!(Tm::on_initialize(4), 1 + 22);
+            assert_eq!(LegacyAnchors::<Test>::iter().count(), 2);
+            assert_eq!(LegacySuspendedLeaves::iter().count(), 1);
+            // the double maps only hold entries they can decode
+            assert_eq!(
+                Anchors::<Test>::iter().collect::<Vec<_>>(),
+                vec![(auths.clone(), c.clone(), Revokable::NotRevoked(1))]
+            );
+            assert_eq!(SuspendedLeaves::<Test>::iter().count(), 0);
+
+            // upgrading storage that is up to date only reads the version
+            assert_eq!(Tm::on_runtime_upgrade(), 1);
+            assert_eq!(StorageVersion::get(), Releases::CURRENT);
+
+            // entries are looked up in both layouts until migrated
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            assert!(Tm::leaf_suspended_by(&auths, &leaf, 10));
+            assert!(!Tm::leaf_suspended_by(&auths, &leaf, 11));
+            assert!(snapshot::export::<Test>().is_err());
+
+            Tm::migrate_legacy_storage(
+                system::RawOrigin::Root.into(),
+                vec![(auths.clone(), a.clone()), (auths.clone(), b.clone())],
+                vec![(auths.clone(), leaf.clone())],
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &c, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(SuspendedLeaves::<Test>::get(&auths, &leaf), Some(10));
+            assert_eq!(snapshot::export::<Test>().unwrap().anchors.len(), 3);
+        });
+
+        let mut ext: sp_io::TestExternalities = GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .into();
+        ext.execute_with(|| {
+            // new chains start out with the current layout
+            assert_eq!(StorageVersion::get(), Releases::CURRENT);
+        });
     }
-    // This function basically just builds a genesis storage key/value store according to
-    // our desired mockup.
-    fn new_test_ext() -> sp_io::TestExternalities {
-        system::GenesisConfig::default()
+
+    fn genesis_ext(config: GenesisConfig<Test>) -> sp_io::TestExternalities {
+        let mut storage = system::GenesisConfig::default()
             .build_storage::<Test>()
-            .unwrap()
-            .into()
+            .unwrap();
+        config.assimilate_storage(&mut storage).unwrap();
+        storage.into()
     }
 
     #[test]
-    fn default_values_are_none() {
+    fn genesis_config() {
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), account_leaf(1)]);
+        let (root, leaf) = (
+            MerkleRoot::from_root([1u8; 32]),
+            Hashed::prehashed([2u8; 32]),
+        );
+        let config = GenesisConfig::<Test> {
+            anchors: vec![(auths.clone(), root.clone(), 0)],
+            suspensions: vec![(auths.clone(), leaf.clone(), 10)],
+            snapshot: None,
+        };
+        genesis_ext(config).execute_with(|| {
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Valid(0))
+            );
+            assert_eq!(Tm::anchors_for_root(&root), vec![auths.clone()]);
+            assert_eq!(SuspendedLeaves::<Test>::get(&auths, &leaf), Some(10));
+            // genesis anchors can be revoked like any other
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                root.clone(),
+                proofs[0].clone().into(),
+            )
+            .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Revoked)
+            );
+        });
+    }
+
+    #[test]
+    fn genesis_config_from_json() {
+        let (admins, root, leaf) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let json = format!(
+            r#"{{ "anchors": [[{:?}, {:?}, 5]], "suspensions": [[{:?}, {:?}, 10]] }}"#,
+            admins, root, admins, leaf
+        );
+        let config = GenesisConfig::<Test>::from_json(&json).unwrap();
+        let admins = MerkleRoot::from_root(admins);
+        assert_eq!(
+            config.anchors,
+            vec![(admins.clone(), MerkleRoot::from_root(root), 5)]
+        );
+        assert_eq!(
+            config.suspensions,
+            vec![(admins, Hashed::prehashed(leaf), 10)]
+        );
+        GenesisConfig::<Test>::from_json(r#"{ "anchors": [[[1, 2], [3], 0]] }"#)
+            .expect_err("Accepted a malformed root.");
+    }
+
+    #[test]
+    fn snapshot() {
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), account_leaf(1)]);
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let b = MerkleRoot::from_root([2u8; 32]);
+        let c = MerkleRoot::from_root([4u8; 32]);
+        let validity = Validity {
+            not_before: 5,
+            not_after: 10,
+        };
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([3u8; 32]);
+
+        let exported = new_test_ext().execute_with(|| {
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![a.clone(), b.clone()])
+                .unwrap();
+            Tm::revoke_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                b.clone(),
+                proofs[0].clone().into(),
+            )
+            .unwrap();
+            Tm::suspend_leaf(
+                Origin::signed(1),
+                proofs[1].clone().into(),
+                auths.clone(),
+                leaf.clone(),
+                10,
+            )
+            .unwrap();
+            Tm::create_anchor(Origin::signed(0), auths.clone(), c.clone(), Some(validity)).unwrap();
+            Tm::delegate(
+                Origin::signed(0),
+                auths.clone(),
+                proofs[0].clone().into(),
+                5,
+                Role::Revoker,
+                100,
+            )
+            .unwrap();
+            snapshot::export::<Test>().unwrap()
+        });
+        assert_eq!(exported.anchors.len(), 3);
+        assert_eq!(
+            exported.validity,
+            vec![(auths.clone(), c.clone(), validity)]
+        );
+        assert_eq!(exported.delegations.len(), 1);
+        assert_eq!(
+            exported.suspensions,
+            vec![(auths.clone(), leaf.clone(), 10)]
+        );
+        assert!(exported.checksum_valid::<Blake2s>());
+
+        // both encodings round trip
+        let json = exported.to_json().unwrap();
+        assert_eq!(SnapshotOf::<Test>::from_json(&json).unwrap(), exported);
+        let scale = exported.encode();
+        assert_eq!(
+            SnapshotOf::<Test>::decode(&mut &scale[..]).unwrap(),
+            exported
+        );
+
         new_test_ext().execute_with(|| {
-            let auth = MerkleRoot::from_root([0u8; 32]);
-            let docs = MerkleRoot::from_root([0u8; 32]);
-            let key = (auth, docs);
-            assert!(!Anchors::<Test>::exists(&key));
-            assert_eq!(Anchors::<Test>::get(&key), None);
+            Tm::import_anchors(Origin::signed(0), exported.clone())
+                .expect_err("Imported without root origin.");
+            let mut dropped = exported.clone();
+            dropped.anchors.pop();
+            Tm::import_anchors(system::RawOrigin::Root.into(), dropped)
+                .expect_err("Imported a snapshot with a missing entry.");
+            let mut dropped = exported.clone();
+            dropped.validity.clear();
+            Tm::import_anchors(system::RawOrigin::Root.into(), dropped)
+                .expect_err("Imported a snapshot with a missing validity period.");
+            Tm::import_anchors(system::RawOrigin::Root.into(), exported.clone()).unwrap();
+            assert_eq!(snapshot::export::<Test>().unwrap(), exported);
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &a, 0),
+                Some(AnchorStatus::Valid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &b, 0),
+                Some(AnchorStatus::Revoked)
+            );
+            assert_eq!(Tm::anchors_for_root(&b), vec![auths.clone()]);
+            // the validity period survives the round trip
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &c, 0),
+                Some(AnchorStatus::NotYetValid(1))
+            );
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &c, 11),
+                Some(AnchorStatus::Expired(1))
+            );
+            Tm::revoke_anchor(Origin::signed(5), auths.clone(), a.clone(), vec![].into()).unwrap();
+        });
 
-            let auth = MerkleRoot::from_root([0u8; 32]);
-            let doc = Hashed::prehashed([0u8; 32]);
-            let key = (auth, doc);
-            assert!(!SuspendedLeaves::<Test>::exists(&key));
-            assert_eq!(SuspendedLeaves::<Test>::get(&key), None);
+        let config = GenesisConfig::<Test> {
+            anchors: vec![],
+            suspensions: vec![],
+            snapshot: Some(exported.clone()),
+        };
+        genesis_ext(config).execute_with(|| {
+            assert_eq!(snapshot::export::<Test>().unwrap(), exported);
         });
     }
 
     #[test]
-    fn anchor() {
+    fn import_rotations() {
+        type Admins = MerkleRoot<u64, [u8; 32]>;
+        type Root = MerkleRoot<Document, [u8; 32]>;
+        let x: Admins = MerkleRoot::from_root([5u8; 32]);
+        let y: Admins = MerkleRoot::from_root([6u8; 32]);
+        let z: Admins = MerkleRoot::from_root([7u8; 32]);
+        let root: Root = MerkleRoot::from_root([1u8; 32]);
+        let other: Root = MerkleRoot::from_root([2u8; 32]);
+
         new_test_ext().execute_with(|| {
-            let (auths, root) = Default::default();
-            assert_eq!(Tm::lookup_anchor(&auths, &root), None);
-            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone()).unwrap();
-            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone())
-                .expect_err("Was able to anchor twice.");
-            Tm::create_anchor(Origin::signed(1), auths.clone(), root.clone())
-                .expect_err("Was able to anchor twice.");
+            Tm::create_anchor(Origin::signed(0), x.clone(), root.clone(), None).unwrap();
+            let empty = snapshot::export::<Test>().unwrap();
+            let import = |anchors: &[(&Admins, &Root)], rotations: &[(&Admins, &Root, &Admins)]| {
+                let mut snapshot = empty.clone();
+                snapshot.anchors = anchors
+                    .iter()
+                    .map(|&(a, r)| (a.clone(), r.clone(), Revokable::NotRevoked(1)))
+                    .collect();
+                snapshot.rotations = rotations
+                    .iter()
+                    .map(|&(o, r, n)| (o.clone(), r.clone(), n.clone(), 1))
+                    .collect();
+                Tm::import_anchors(system::RawOrigin::Root.into(), snapshot.seal::<Blake2s>())
+            };
+            let invalid = Err(Error::<Test>::InvalidRotation.into());
+
+            // the root is still anchored under the set it was rotated away from
+            assert_eq!(import(&[], &[(&x, &root, &y)]), invalid);
+            assert_eq!(import(&[(&y, &other)], &[(&y, &other, &x)]), invalid);
+            // the rotation leads nowhere
+            assert_eq!(import(&[], &[(&y, &other, &z)]), invalid);
+            // the rotations form a cycle
+            assert_eq!(import(&[], &[(&y, &other, &z), (&z, &other, &y)]), invalid);
+            assert_eq!(import(&[], &[(&y, &root, &z), (&z, &root, &y)]), invalid);
+            // nothing was written by the refused imports
+            assert_eq!(snapshot::export::<Test>().unwrap(), empty);
+
+            // rotations to a stored or imported anchor, directly or through other rotations
+            assert_eq!(import(&[], &[(&y, &root, &x)]), Ok(()));
             assert_eq!(
-                Tm::lookup_anchor(&auths, &root),
-                Some(Revokable::NotRevoked(1))
+                import(&[(&x, &other)], &[(&z, &other, &y), (&y, &other, &x)]),
+                Ok(())
             );
+            assert_eq!(Tm::current_admins(&z, &other), x);
+            assert_eq!(Tm::current_admins(&y, &root), x);
+            // another admin set is already rotated to the target
+            assert_eq!(import(&[], &[(&z, &root, &x)]), invalid);
+        });
+    }
+
+    #[test]
+    fn force_revoke_and_remove() {
+        new_test_ext().execute_with(|| {
+            // anchored under the irrevocable admin set
+            let (auths, root) = Default::default();
+            let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([1u8; 32]);
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
 
-            Tm::revoke_anchor(Origin::signed(0), auths.clone(), root.clone(), vec![]).expect_err(
-                "Since auths represents the empty set, the root should be irrevocable.",
+            Tm::force_revoke_anchor(Origin::signed(0), auths.clone(), root.clone())
+                .expect_err("Force revoked without ForceOrigin.");
+            Tm::force_revoke_anchor(system::RawOrigin::Root.into(), auths.clone(), root.clone())
+                .unwrap();
+            assert_eq!(
+                Tm::lookup_anchor(&auths, &root, 0),
+                Some(AnchorStatus::Revoked)
             );
+            Tm::force_revoke_anchor(system::RawOrigin::Root.into(), auths.clone(), root.clone())
+                .expect_err("Force revoked twice.");
+
+            Tm::force_suspend_leaf(Origin::signed(0), auths.clone(), leaf.clone(), 10)
+                .expect_err("Force suspended without ForceOrigin.");
+            Tm::force_suspend_leaf(
+                system::RawOrigin::Root.into(),
+                auths.clone(),
+                leaf.clone(),
+                10,
+            )
+            .unwrap();
+            assert_eq!(SuspendedLeaves::<Test>::get(&auths, &leaf), Some(10));
+            Tm::force_suspend_leaf(
+                system::RawOrigin::Root.into(),
+                auths.clone(),
+                leaf.clone(),
+                5,
+            )
+            .expect_err("Shortened a suspension.");
+
+            Tm::force_remove(Origin::signed(0), auths.clone(), root.clone())
+                .expect_err("Force removed without ForceOrigin.");
+            Tm::force_remove(system::RawOrigin::Root.into(), auths.clone(), root.clone()).unwrap();
+            assert_eq!(Tm::lookup_anchor(&auths, &root, 0), None);
+            assert!(Tm::anchors_for_root(&root).is_empty());
+            Tm::force_remove(system::RawOrigin::Root.into(), auths.clone(), root.clone())
+                .expect_err("Removed an anchor that does not exist.");
+            // the root may be anchored again
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
         });
     }
 
     #[test]
-    fn revoke_anchor() {
-        // a merkle root representing { 0u64 }
-        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
-        let docs = Default::default();
+    fn force_remove_rotations() {
+        // merkle roots representing { 0u64 } and { 1u64 }
+        let auths0 = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
+        let auths1 = MerkleRoot::from_root(blake(blake(&1u64.to_be_bytes()[..])));
+        let auths2 = MerkleRoot::from_root([9u8; 32]);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let rotate =
+            |signer: u64, old: &MerkleRoot<u64, [u8; 32]>, new: &MerkleRoot<u64, [u8; 32]>| {
+                Tm::rotate_admins(
+                    Origin::signed(signer),
+                    old.clone(),
+                    new.clone(),
+                    root.clone(),
+                    vec![].into(),
+                    vec![],
+                )
+            };
 
         new_test_ext().execute_with(|| {
-            assert_eq!(Tm::lookup_anchor(&auths, &docs), None);
-            Tm::create_anchor(Origin::signed(0), auths.clone(), docs.clone()).unwrap();
-            assert_eq!(
-                Tm::lookup_anchor(&auths, &docs),
-                Some(Revokable::NotRevoked(1))
-            );
-            Tm::revoke_anchor(Origin::signed(0), auths.clone(), docs.clone(), vec![]).unwrap();
-            assert_eq!(Tm::lookup_anchor(&auths, &docs), Some(Revokable::Revoked));
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap();
+            rotate(0, &auths0, &auths1).unwrap();
+            rotate(1, &auths1, &auths2).unwrap();
+            assert_eq!(Tm::current_admins(&auths0, &root), auths2);
+
+            Tm::force_remove(system::RawOrigin::Root.into(), auths2.clone(), root.clone()).unwrap();
+            // the admin sets the root was rotated away from no longer lead anywhere
+            assert_eq!(Tm::current_admins(&auths0, &root), auths0);
+            assert_eq!(Tm::current_admins(&auths1, &root), auths1);
+            assert_eq!(AdminRotations::<Test>::iter().count(), 0);
+            assert_eq!(RotatedFrom::<Test>::iter().count(), 0);
+
+            // anchoring the root again does not revive the rotations
+            Tm::create_anchor(Origin::signed(0), auths2.clone(), root.clone(), None).unwrap();
+            assert_eq!(Tm::current_admins(&auths0, &root), auths0);
+            assert_eq!(Tm::lookup_anchor(&auths0, &root, 0), None);
+            // and the old admin sets may anchor it again and rotate it anew
+            Tm::create_anchor(Origin::signed(0), auths0.clone(), root.clone(), None).unwrap();
+            rotate(0, &auths0, &auths1).unwrap();
+            assert_eq!(Tm::current_admins(&auths0, &root), auths1);
+        });
+    }
+
+    #[test]
+    fn force_remove_supersessions() {
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), account_leaf(1)]);
+        let v1 = MerkleRoot::from_root([1u8; 32]);
+        let v2 = MerkleRoot::from_root([2u8; 32]);
+        let v3 = MerkleRoot::from_root([3u8; 32]);
+        let supersede = |old: &MerkleRoot<Document, [u8; 32]>,
+                         new: &MerkleRoot<Document, [u8; 32]>| {
+            Tm::supersede_anchor(
+                Origin::signed(0),
+                auths.clone(),
+                old.clone(),
+                new.clone(),
+                proofs[0].clone().into(),
+            )
+        };
+
+        new_test_ext().execute_with(|| {
+            Tm::create_anchor(Origin::signed(0), auths.clone(), v1.clone(), None).unwrap();
+            supersede(&v1, &v2).unwrap();
+
+            // removing the new version leaves the old one without a successor
+            Tm::force_remove(system::RawOrigin::Root.into(), auths.clone(), v2.clone()).unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v1);
+            Tm::create_anchor(Origin::signed(0), auths.clone(), v2.clone(), None).unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v1);
+            supersede(&v1, &v3).unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v3);
+
+            // removing the old version leaves the new one without a predecessor
+            Tm::force_remove(system::RawOrigin::Root.into(), auths.clone(), v1.clone()).unwrap();
+            assert!(!PreviousVersions::<Test>::contains_key((&auths, &v3)));
+            Tm::create_anchor(Origin::signed(0), auths.clone(), v1.clone(), None).unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v1);
+            // the re-anchored root may be superseded again
+            Tm::force_remove(system::RawOrigin::Root.into(), auths.clone(), v3.clone()).unwrap();
+            supersede(&v1, &v3).unwrap();
+            assert_eq!(Tm::current_version(&auths, &v1), v3);
         });
+    }
+
+    #[test]
+    fn anchor_origin() {
+        let a = MerkleRoot::from_root([1u8; 32]);
+        let b = MerkleRoot::from_root([2u8; 32]);
+        let c = MerkleRoot::from_root([3u8; 32]);
+        let auths: MerkleRoot<u64, [u8; 32]> = Default::default();
 
         new_test_ext().execute_with(|| {
-            Tm::revoke_anchor(Origin::signed(0), auths.clone(), docs.clone(), vec![]).unwrap();
-            Tm::create_anchor(Origin::signed(0), auths.clone(), docs.clone()).unwrap_err();
+            set_anchor_policy(AnchorPolicy::AnySigned);
+            Tm::create_anchor(
+                system::RawOrigin::Root.into(),
+                auths.clone(),
+                a.clone(),
+                None,
+            )
+            .expect_err("Anchored without a signed origin.");
+            Tm::create_anchor(Origin::signed(5), auths.clone(), a.clone(), None).unwrap();
         });
 
         new_test_ext().execute_with(|| {
-            Tm::revoke_anchor(Origin::signed(0), auths.clone(), docs.clone(), vec![]).unwrap();
-            Tm::revoke_anchor(Origin::signed(0), auths.clone(), docs.clone(), vec![]).unwrap_err();
+            set_anchor_policy(AnchorPolicy::Whitelist);
+            Tm::create_anchor(Origin::signed(5), auths.clone(), a.clone(), None)
+                .expect_err("Anchored from an account that is not an issuer.");
+            Tm::create_anchors(Origin::signed(5), auths.clone(), vec![b.clone()])
+                .expect_err("Anchored from an account that is not an issuer.");
+            let commitment = anchor_commitment::<Test>(&auths, &c, &[0u8; 32]);
+            Tm::commit_anchor(Origin::signed(5), commitment.clone())
+                .expect_err("Committed from an account that is not an issuer.");
+            Tm::create_anchor(Origin::signed(1), auths.clone(), a.clone(), None).unwrap();
+            Tm::create_anchors(Origin::signed(0), auths.clone(), vec![b.clone()]).unwrap();
+            Tm::commit_anchor(Origin::signed(1), commitment).unwrap();
+            Tm::reveal_anchor(Origin::signed(1), auths.clone(), c.clone(), [0u8; 32]).unwrap();
         });
 
         new_test_ext().execute_with(|| {
-            Tm::revoke_anchor(
-                Origin::signed(0),
+            set_anchor_policy(AnchorPolicy::Collective);
+            Tm::create_anchor(Origin::signed(0), auths.clone(), a.clone(), None)
+                .expect_err("Anchored without the collective.");
+            Tm::create_anchor(
+                system::RawOrigin::Root.into(),
                 auths.clone(),
-                MerkleRoot::from_root([0; 32]),
-                vec![],
+                a.clone(),
+                None,
             )
             .unwrap();
-            Tm::revoke_anchor(
+            Tm::create_anchors(
+                system::RawOrigin::Root.into(),
+                auths.clone(),
+                vec![b.clone()],
+            )
+            .unwrap();
+            // commitments are bound to an account, a collective can not make them
+            let commitment = anchor_commitment::<Test>(&auths, &c, &[0u8; 32]);
+            Tm::commit_anchor(system::RawOrigin::Root.into(), commitment)
+                .expect_err("Committed without an account.");
+        });
+        set_anchor_policy(AnchorPolicy::AnySigned);
+    }
+
+    #[test]
+    fn anchor_quotas() {
+        let roots = |from: u32, to: u32| -> Vec<MerkleRoot<Document, [u8; 32]>> {
+            (from..to)
+                .map(|i| MerkleRoot::from_root(blake(i as u64)))
+                .collect()
+        };
+        let auths: MerkleRoot<u64, [u8; 32]> = Default::default();
+
+        new_test_ext().execute_with(|| {
+            system::Module::<Test>::set_block_number(1);
+            Tm::create_anchors(Origin::signed(0), auths.clone(), roots(0, 256)).unwrap();
+            Tm::create_anchors(Origin::signed(1), auths.clone(), roots(256, 300)).unwrap();
+            assert_eq!(
+                Tm::create_anchor(
+                    Origin::signed(2),
+                    auths.clone(),
+                    roots(300, 301)[0].clone(),
+                    None
+                ),
+                Err(Error::<Test>::BlockQuotaExceeded.into())
+            );
+
+            // the block quota starts over with each block, the account quota does not
+            system::Module::<Test>::set_block_number(2);
+            Tm::create_anchors(Origin::signed(0), auths.clone(), roots(300, 444)).unwrap();
+            assert_eq!(
+                Tm::create_anchors(Origin::signed(0), auths.clone(), roots(444, 445)),
+                Err(Error::<Test>::AccountQuotaExceeded.into())
+            );
+            // a failed call uses no quota
+            assert_eq!(RecentAnchors::<Test>::get(0), vec![(1, 256), (2, 144)]);
+            Tm::create_anchor(
+                Origin::signed(2),
+                auths.clone(),
+                roots(444, 445)[0].clone(),
+                None,
+            )
+            .unwrap();
+
+            // anchors of block 1 leave the window of account 0 at block 11
+            system::Module::<Test>::set_block_number(10);
+            assert_eq!(
+                Tm::create_anchor(
+                    Origin::signed(0),
+                    auths.clone(),
+                    roots(445, 446)[0].clone(),
+                    None
+                ),
+                Err(Error::<Test>::AccountQuotaExceeded.into())
+            );
+            system::Module::<Test>::set_block_number(11);
+            Tm::create_anchor(
                 Origin::signed(0),
                 auths.clone(),
-                MerkleRoot::from_root([1; 32]),
-                vec![],
+                roots(445, 446)[0].clone(),
+                None,
             )
             .unwrap();
+            assert_eq!(RecentAnchors::<Test>::get(0), vec![(2, 144), (11, 1)]);
+
+            // the entries of accounts that stopped anchoring are removed once they leave the
+            // window, entries still in use are kept
+            Tm::on_initialize(11);
+            assert!(RecentAnchors::<Test>::contains_key(0));
+            assert!(!RecentAnchors::<Test>::contains_key(1));
+            assert!(RecentAnchors::<Test>::contains_key(2));
+            Tm::on_initialize(12);
+            assert!(RecentAnchors::<Test>::contains_key(0));
+            assert!(!RecentAnchors::<Test>::contains_key(2));
+            Tm::on_initialize(21);
+            assert_eq!(RecentAnchors::<Test>::iter().count(), 0);
+            assert_eq!(QuotaExpiries::<Test>::iter().count(), 0);
         });
     }
 
     #[test]
-    fn suspend_leaf() {
-        // a merkle root representing { 0u64 }
-        let auths = MerkleRoot::from_root(blake(blake(&0u64.to_be_bytes()[..])));
-        let doc: Hashed<Document, [u8; 32]> = Default::default();
+    fn successful_revocations_are_free() {
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), account_leaf(1)]);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([2u8; 32]);
+        let free = Ok(Pays::No.into());
 
-        let ua = Origin::signed(0);
-        let ub = Origin::signed(1);
+        new_test_ext().execute_with(|| {
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
+            let failed = Tm::revoke_anchor(
+                Origin::signed(2),
+                auths.clone(),
+                root.clone(),
+                proofs[0].clone().into(),
+            )
+            .unwrap_err();
+            assert_eq!(failed.post_info.pays_fee, Pays::Yes);
+            assert_eq!(
+                Tm::revoke_anchor(
+                    Origin::signed(0),
+                    auths.clone(),
+                    root.clone(),
+                    proofs[0].clone().into()
+                ),
+                free
+            );
+
+            let failed = Tm::suspend_leaf(
+                Origin::signed(2),
+                proofs[1].clone().into(),
+                auths.clone(),
+                leaf.clone(),
+                10,
+            )
+            .unwrap_err();
+            assert_eq!(failed.post_info.pays_fee, Pays::Yes);
+            // a new suspension adds an entry to storage, extending it does not
+            assert_eq!(
+                Tm::suspend_leaf(
+                    Origin::signed(1),
+                    proofs[1].clone().into(),
+                    auths.clone(),
+                    leaf.clone(),
+                    10
+                ),
+                Ok(Pays::Yes.into())
+            );
+            assert_eq!(
+                Tm::suspend_leaves(
+                    Origin::signed(1),
+                    proofs[1].clone().into(),
+                    auths.clone(),
+                    vec![(leaf.clone(), 20)]
+                ),
+                free
+            );
+            assert_eq!(
+                Tm::suspend_leaves(
+                    Origin::signed(1),
+                    proofs[1].clone().into(),
+                    auths.clone(),
+                    vec![(leaf.clone(), 30), (Hashed::prehashed([3u8; 32]), 30)]
+                ),
+                Ok(Pays::Yes.into())
+            );
+        });
+    }
+
+    #[test]
+    fn self_made_admins_pay() {
+        // an admin set made up on the spot, without anchors
+        let (auths, proofs) = merkle_tree(&[account_leaf(2), account_leaf(3)]);
+        let root = MerkleRoot::from_root([1u8; 32]);
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([2u8; 32]);
+        let paid = Ok(Pays::Yes.into());
 
         new_test_ext().execute_with(|| {
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), false);
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), false);
-            Tm::suspend_leaf(ua.clone(), vec![], auths.clone(), doc.clone(), 10).unwrap();
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), true);
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), false);
-            Tm::suspend_leaf(ua.clone(), vec![], auths.clone(), doc.clone(), 11).unwrap();
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 10), true);
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 11), true);
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 12), false);
+            assert_eq!(
+                Tm::revoke_anchor(
+                    Origin::signed(2),
+                    auths.clone(),
+                    root.clone(),
+                    proofs[0].clone().into()
+                ),
+                paid
+            );
+            // owning an entry in `Anchors` does not make new entries free
+            assert_eq!(
+                Tm::revoke_anchor(
+                    Origin::signed(2),
+                    auths.clone(),
+                    MerkleRoot::from_root([3u8; 32]),
+                    proofs[0].clone().into()
+                ),
+                paid
+            );
+            assert_eq!(
+                Tm::suspend_leaf(
+                    Origin::signed(2),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    leaf.clone(),
+                    10
+                ),
+                paid
+            );
+            // only changes to the entries the set paid for are
+            assert_eq!(
+                Tm::suspend_leaf(
+                    Origin::signed(2),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    leaf.clone(),
+                    20
+                ),
+                Ok(Pays::No.into())
+            );
+        });
 
-            // Revoke
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                Tm::suspend_leaf(
+                    Origin::signed(2),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    leaf.clone(),
+                    10
+                ),
+                paid
+            );
+            assert_eq!(
+                Tm::suspend_leaves(
+                    Origin::signed(3),
+                    proofs[1].clone().into(),
+                    auths.clone(),
+                    vec![(leaf.clone(), 20)]
+                ),
+                paid
+            );
+        });
+    }
+
+    #[test]
+    fn oversized_proofs() {
+        // an admin set with account 0 as its only member at depth `len`
+        let admins = |len: usize| {
+            let path: Vec<ProofElement<[u8; 32]>> = (0..len)
+                .map(|i| ProofElement::Right([i as u8; 32]))
+                .collect();
+            let root = path
+                .iter()
+                .fold(blake(account_leaf(0)), |node, element| match element {
+                    ProofElement::Right(sibling) => blake((node, *sibling)),
+                    ProofElement::Left(sibling) => blake((*sibling, node)),
+                });
+            let proof: AdminProof<[u8; 32]> = path.into();
+            (MerkleRoot::from_root(root), proof)
+        };
+        let leaf: Hashed<Document, [u8; 32]> = Hashed::prehashed([1u8; 32]);
+
+        new_test_ext().execute_with(|| {
+            let (auths, longest) = admins(MAX_PROOF_SIZE);
+            Tm::suspend_leaf(Origin::signed(0), longest.clone(), auths, leaf.clone(), 10).unwrap();
+            let (auths, proof) = admins(MAX_PROOF_SIZE + 1);
+            assert_eq!(
+                Tm::suspend_leaf(Origin::signed(0), proof.clone(), auths, leaf.clone(), 10),
+                Err(Error::<Test>::ProofTooLarge.into())
+            );
+            // refused before hashing, so weighed no more than the longest proof accepted
+            assert_eq!(proof.weight(), longest.weight());
+            assert_eq!(
+                longest.weight(),
+                PER_HASH_WEIGHT * 2 * (MAX_PROOF_SIZE as Weight + 1)
+            );
+        });
+    }
+
+    #[test]
+    fn verify_documents() {
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), account_leaf(1)]);
+        let leaves: Vec<Hashed<Document, [u8; 32]>> =
+            (0..5u64).map(|i| Hashed::prehashed(blake(i))).collect();
+        let tree = merkle::MerkleTree::new::<Blake2s>(&leaves);
+        let root = tree.root();
+        let proof = tree.multiproof(&[0, 3, 4]);
+        let proven = [leaves[0].clone(), leaves[3].clone(), leaves[4].clone()];
+
+        new_test_ext().execute_with(|| {
+            assert_eq!(
+                Tm::verify_documents(&auths, &root, &proof, &proven, 0),
+                vec![DocumentStatus::NotAnchored; 3]
+            );
+            Tm::create_anchor(Origin::signed(0), auths.clone(), root.clone(), None).unwrap();
             Tm::suspend_leaf(
-                ua.clone(),
-                vec![],
+                Origin::signed(0),
+                proofs[0].clone().into(),
                 auths.clone(),
-                doc.clone(),
-                u64::max_value(),
+                leaves[3].clone(),
+                10,
             )
             .unwrap();
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, 12), true);
-            assert_eq!(Tm::leaf_suspended_by(&auths, &doc, u64::max_value()), true);
+            assert_eq!(
+                Tm::verify_documents(&auths, &root, &proof, &proven, 0),
+                vec![
+                    DocumentStatus::Valid,
+                    DocumentStatus::Suspended,
+                    DocumentStatus::Valid
+                ]
+            );
+            // agrees with verify_document on single proofs from the same tree
+            for (i, leaf) in (0..).zip(&leaves) {
+                assert_eq!(
+                    Tm::verify_document(&auths, &root, &tree.proof(i).unwrap(), leaf, 0),
+                    Tm::verify_documents(&auths, &root, &tree.multiproof(&[i]), &[leaf.clone()], 0)
+                        [0]
+                );
+            }
+            let wrong = [leaves[0].clone(), leaves[2].clone(), leaves[4].clone()];
+            assert_eq!(
+                Tm::verify_documents(&auths, &root, &proof, &wrong, 0),
+                vec![DocumentStatus::NotIncluded; 3]
+            );
         });
+    }
+
+    #[test]
+    fn tree_hash_fits_sparse_trees() {
+        Tm::integrity_test();
+    }
+
+    #[test]
+    fn revocation_registry() {
+        let (auths, proofs) = merkle_tree(&[
+            role_leaf(0, Role::Suspender),
+            role_leaf(1, Role::Reinstater),
+        ]);
+        let leaves: Vec<Hashed<Document, [u8; 32]>> =
+            (0..4u64).map(|i| Hashed::prehashed(blake(i))).collect();
+        let mut tree = sparse::SparseMerkleTree::<Blake2s, Document, [u8; 32]>::new();
 
         new_test_ext().execute_with(|| {
-            Tm::suspend_leaf(ua.clone(), vec![], auths.clone(), doc.clone(), 0).unwrap();
-            Tm::suspend_leaf(ua.clone(), vec![], auths.clone(), doc.clone(), 0).unwrap_err();
+            assert_eq!(Tm::revocation_root(&auths), tree.root());
+            assert_eq!(
+                Tm::revoked_in_registry(&auths, &leaves[0], &tree.proof(&leaves[0])),
+                Some(false)
+            );
+
+            // proofs of later updates are against the root left by earlier ones
+            let mut updates = Vec::new();
+            for leaf in &leaves[..2] {
+                updates.push((leaf.clone(), tree.proof(leaf), true));
+                tree.insert(leaf);
+            }
+            let stale = updates[1].clone();
+
+            // an empty batch is refused, even for a member
+            for signer in [0, 2].iter() {
+                assert_eq!(
+                    Tm::update_revocation_root(
+                        Origin::signed(*signer),
+                        proofs[0].clone().into(),
+                        auths.clone(),
+                        vec![],
+                    ),
+                    Err(Error::<Test>::EmptyBatch.into())
+                );
+            }
+            assert_eq!(Tm::revocation_root(&auths), tree.root());
+
+            // the reinstater may not revoke
+            Tm::update_revocation_root(
+                Origin::signed(1),
+                proofs[1].clone().into(),
+                auths.clone(),
+                updates.clone(),
+            )
+            .unwrap_err();
+            // the admin set owns no anchors, so the update is charged
+            assert_eq!(
+                Tm::update_revocation_root(
+                    Origin::signed(0),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    updates,
+                ),
+                Ok(Pays::Yes.into())
+            );
+            assert_eq!(Tm::revocation_root(&auths), tree.root());
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert_eq!(
+                    Tm::revoked_in_registry(&auths, leaf, &tree.proof(leaf)),
+                    Some(i < 2)
+                );
+            }
+            // a proof of a revoked leaf does not show it is not revoked
+            let mut forged = tree.proof(&leaves[0]);
+            forged.siblings.clear();
+            assert_eq!(Tm::revoked_in_registry(&auths, &leaves[0], &forged), None);
+
+            // a proof against an outdated root is rejected
+            Tm::update_revocation_root(
+                Origin::signed(0),
+                proofs[0].clone().into(),
+                auths.clone(),
+                vec![(leaves[2].clone(), stale.1, true)],
+            )
+            .unwrap_err();
+            assert_eq!(Tm::revocation_root(&auths), tree.root());
+
+            let update = (leaves[0].clone(), tree.proof(&leaves[0]), false);
+            tree.remove(&leaves[0]);
+            Tm::update_revocation_root(
+                Origin::signed(0),
+                proofs[0].clone().into(),
+                auths.clone(),
+                vec![update.clone()],
+            )
+            .unwrap_err();
+            Tm::create_anchor(Origin::signed(0), auths.clone(), Default::default(), None).unwrap();
+            assert_eq!(
+                Tm::update_revocation_root(
+                    Origin::signed(1),
+                    proofs[1].clone().into(),
+                    auths.clone(),
+                    vec![update],
+                ),
+                Ok(Pays::No.into())
+            );
+            assert_eq!(Tm::revocation_root(&auths), tree.root());
+            assert_eq!(
+                Tm::revoked_in_registry(&auths, &leaves[0], &tree.proof(&leaves[0])),
+                Some(false)
+            );
+
+            // other admin sets have their own registry
+            let (other, _) = merkle_tree(&[account_leaf(3)]);
+            assert_eq!(
+                Tm::revocation_root(&other),
+                SparseMerkleRoot::empty::<Blake2s>()
+            );
         });
+    }
+
+    #[test]
+    fn status_list() {
+        use crate::status_list::{hash_compressed, Bitstring, LeafIndex};
+
+        let (auths, proofs) = merkle_tree(&[account_leaf(0), role_leaf(1, Role::Suspender)]);
+        let preimages: Vec<[u8; 32]> = (0..4u64).map(blake).collect();
+        let (docs, _) = merkle_tree::<Document>(&preimages);
+        // the issuer indexes the leaves of its anchored tree by position
+        let leaves: Vec<Hashed<Document, [u8; 32]>> = preimages
+            .iter()
+            .map(|preimage| Hashed::prehashed(blake(preimage)))
+            .collect();
+        let index = LeafIndex::from_trees(vec![&leaves[..]]);
+        let mut list = Bitstring::new(index.len());
+        list.set(index.index_of(&leaves[1]).unwrap(), true);
+        let compressed = list.compress();
+        let hash = hash_compressed::<Blake2s, [u8; 32]>(&compressed);
 
         new_test_ext().execute_with(|| {
-            Tm::suspend_leaf(ub.clone(), vec![], auths.clone(), doc.clone(), 0).unwrap_err();
+            assert_eq!(Tm::status_list(&auths), None);
+            // role scoped leaves could only revoke or only reinstate
+            Tm::update_status_list(
+                Origin::signed(1),
+                proofs[1].clone().into(),
+                auths.clone(),
+                hash.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                Tm::update_status_list(
+                    Origin::signed(0),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    hash.clone()
+                ),
+                Ok(Pays::Yes.into())
+            );
+            assert_eq!(Tm::status_list(&auths), Some(hash.clone()));
+
+            // once the admins anchored documents, lists are free
+            Tm::create_anchor(Origin::signed(0), auths.clone(), docs, None).unwrap();
+            assert_eq!(
+                Tm::update_status_list(
+                    Origin::signed(0),
+                    proofs[0].clone().into(),
+                    auths.clone(),
+                    hash.clone()
+                ),
+                Ok(Pays::No.into())
+            );
+
+            // what a verifier does with the published list
+            let published = Tm::status_list(&auths).unwrap();
+            assert_eq!(hash_compressed::<Blake2s, [u8; 32]>(&compressed), published);
+            let list = Bitstring::decompress(&compressed).unwrap();
+            assert_eq!(index.is_revoked(&list, &leaves[1]), Some(true));
+            assert_eq!(index.is_revoked(&list, &leaves[2]), Some(false));
         });
     }
 }
@@ -4,10 +4,22 @@ use crate::fromalt::FromAlt;
 use crate::hasher::{Hash, Hashed};
 use blake2::digest::{generic_array::GenericArray, Digest};
 use codec::{Decode, Encode};
+use core::convert::TryFrom;
 use core::{fmt::Debug, marker::PhantomData};
 use derivative::Derivative;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::{vec, vec::Vec};
 
 #[derive(Encode, Decode, Derivative)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "std",
+    serde(
+        transparent,
+        bound(serialize = "O: Serialize", deserialize = "O: Deserialize<'de>")
+    )
+)]
 #[derivative(
     Clone(bound = "O: Clone"),
     PartialEq(bound = "O: PartialEq"),
@@ -20,6 +32,7 @@ use derivative::Derivative;
 )]
 pub struct MerkleRoot<T, O> {
     hash: O,
+    #[cfg_attr(feature = "std", serde(skip))]
     _spook: PhantomData<T>,
 }
 
@@ -30,6 +43,12 @@ impl<T, O> MerkleRoot<T, O> {
     }
 }
 
+impl<T, O: Hash> Hash for MerkleRoot<T, O> {
+    fn hash<D: Digest>(&self, hasher: &mut D) {
+        self.hash.hash(hasher)
+    }
+}
+
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
 pub enum ProofElement<O> {
     Left(O),
@@ -65,6 +84,359 @@ where
     expected_root == root.hash
 }
 
+/// The same information as a `Vec<ProofElement<O>>` in less space. Instead of tagging each
+/// sibling hash, the sides are packed into a bitfield.
+///
+/// Bit `i` of `sides` (least significant bit of byte `i / 8` first) is set when `hashes[i]` is
+/// the left sibling. `sides` holds exactly as many bytes as needed, unused bits are zero.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct CompactProof<O> {
+    pub sides: Vec<u8>,
+    pub hashes: Vec<O>,
+}
+
+impl<O> CompactProof<O> {
+    /// Whether `sides` has the expected length and no bits set past the last hash.
+    fn well_formed(&self) -> bool {
+        let len = self.hashes.len();
+        if self.sides.len() != (len + 7) / 8 {
+            return false;
+        }
+        match self.sides.last() {
+            Some(last) if len % 8 != 0 => last >> (len % 8) == 0,
+            _ => true,
+        }
+    }
+
+    /// The proof elements of this proof, in order.
+    fn elements(&self) -> impl Iterator<Item = ProofElement<&O>> {
+        let sides = &self.sides;
+        self.hashes.iter().enumerate().map(move |(i, hash)| {
+            if sides[i / 8] & (1 << (i % 8)) != 0 {
+                ProofElement::Left(hash)
+            } else {
+                ProofElement::Right(hash)
+            }
+        })
+    }
+}
+
+impl<O> From<Vec<ProofElement<O>>> for CompactProof<O> {
+    fn from(proof: Vec<ProofElement<O>>) -> Self {
+        let mut sides = vec![0u8; (proof.len() + 7) / 8];
+        let hashes = proof
+            .into_iter()
+            .enumerate()
+            .map(|(i, pe)| match pe {
+                ProofElement::Left(hash) => {
+                    sides[i / 8] |= 1 << (i % 8);
+                    hash
+                }
+                ProofElement::Right(hash) => hash,
+            })
+            .collect();
+        CompactProof { sides, hashes }
+    }
+}
+
+impl<O> TryFrom<CompactProof<O>> for Vec<ProofElement<O>> {
+    type Error = &'static str;
+
+    /// Fails if `sides` does not match the number of hashes, so that every vector of proof
+    /// elements has exactly one compact form.
+    fn try_from(proof: CompactProof<O>) -> Result<Self, Self::Error> {
+        if !proof.well_formed() {
+            return Err("malformed compact proof");
+        }
+        let sides = proof.sides;
+        Ok(proof
+            .hashes
+            .into_iter()
+            .enumerate()
+            .map(|(i, hash)| {
+                if sides[i / 8] & (1 << (i % 8)) != 0 {
+                    ProofElement::Left(hash)
+                } else {
+                    ProofElement::Right(hash)
+                }
+            })
+            .collect())
+    }
+}
+
+/// Like `verify_proof`, for a proof in compact form. Malformed proofs are refused.
+pub fn verify_compact_proof<H, T, O>(
+    root: &MerkleRoot<T, O>,
+    proof: &CompactProof<O>,
+    leafhash: &Hashed<T, O>,
+) -> bool
+where
+    H: Digest,
+    O: Hash + Eq + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    if !proof.well_formed() {
+        return false;
+    }
+    let lhh: Hashed<Hashed<T, O>, O> = Hashed::from_preimage::<H>(leafhash);
+    let expected_root = proof.elements().fold(lhh.hash, |leaf, pe| match pe {
+        ProofElement::Left(h) => Hashed::from_preimage::<H>(&(h, &leaf)).hash,
+        ProofElement::Right(h) => Hashed::from_preimage::<H>(&(&leaf, h)).hash,
+    });
+    expected_root == root.hash
+}
+
+/// A proof that several leaves are part of the same tree, see `verify_multiproof`.
+///
+/// The tree is the one built by `MerkleTree` over `leaf_count` leaves. `hashes` holds each
+/// sibling needed to recompute the root exactly once, in the order `verify_multiproof` consumes
+/// them: level by level from the leaves up, left to right within a level.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct MultiProof<O> {
+    pub leaf_count: u32,
+    /// Positions of the proven leaves in the tree, strictly ascending.
+    pub indices: Vec<u32>,
+    pub hashes: Vec<O>,
+}
+
+/// Check that `leafhashes[i]` is the leaf at `proof.indices[i]` of the tree with root `root`, for
+/// every i. Proving no leaves at all is refused.
+pub fn verify_multiproof<H, T, O>(
+    root: &MerkleRoot<T, O>,
+    proof: &MultiProof<O>,
+    leafhashes: &[Hashed<T, O>],
+) -> bool
+where
+    H: Digest,
+    O: Hash + Eq + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    let mut width = proof.leaf_count;
+    if leafhashes.is_empty()
+        || leafhashes.len() != proof.indices.len()
+        || proof.indices.windows(2).any(|w| w[0] >= w[1])
+        || proof.indices.last().map_or(true, |last| *last >= width)
+    {
+        return false;
+    }
+    let mut layer: Vec<(u32, O)> = proof
+        .indices
+        .iter()
+        .zip(leafhashes)
+        .map(|(i, leaf)| (*i, Hashed::<Hashed<T, O>, O>::from_preimage::<H>(leaf).hash))
+        .collect();
+    let mut hashes = proof.hashes.iter();
+    while width > 1 {
+        let mut parents = Vec::with_capacity(layer.len());
+        let mut nodes = layer.into_iter().peekable();
+        while let Some((i, node)) = nodes.next() {
+            let sibling = i ^ 1;
+            let parent = if sibling >= width {
+                node
+            } else if nodes.peek().map(|(j, _)| *j) == Some(sibling) {
+                let (_, right) = nodes.next().expect("peeked above");
+                Hashed::from_preimage::<H>(&(node, right)).hash
+            } else {
+                let sibling = match hashes.next() {
+                    Some(hash) => hash,
+                    None => return false,
+                };
+                if i % 2 == 0 {
+                    Hashed::from_preimage::<H>(&(&node, sibling)).hash
+                } else {
+                    Hashed::from_preimage::<H>(&(sibling, &node)).hash
+                }
+            };
+            parents.push((i / 2, parent));
+        }
+        layer = parents;
+        width = (width + 1) / 2;
+    }
+    hashes.next().is_none() && layer.len() == 1 && layer[0].1 == root.hash
+}
+
+/// A proof that a leaf is at a given position of a tree of a given size, see
+/// `verify_indexed_proof`.
+///
+/// `siblings` holds the sibling hashes from the leaf up, skipping the levels at which the node
+/// has no sibling. Which side each sibling is on and which levels are skipped follow from the
+/// index of the leaf and the size of the tree, so a proof for one position does not verify for
+/// any other.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct IndexedProof<O> {
+    pub siblings: Vec<O>,
+}
+
+/// Check that `leafhash` is leaf number `index` of the tree of `size` leaves with root `root`.
+///
+/// `root` must be a root that commits to the size of the tree, see `MerkleTree::sized_root`.
+/// Since the size is committed, a proof can neither be shortened to claim a smaller tree nor
+/// extended to claim a larger one.
+pub fn verify_indexed_proof<H, T, O>(
+    root: &MerkleRoot<T, O>,
+    index: u32,
+    size: u32,
+    proof: &IndexedProof<O>,
+    leafhash: &Hashed<T, O>,
+) -> bool
+where
+    H: Digest,
+    O: Hash + Eq + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    if index >= size {
+        return false;
+    }
+    let mut node = Hashed::<Hashed<T, O>, O>::from_preimage::<H>(leafhash).hash;
+    let mut siblings = proof.siblings.iter();
+    let (mut i, mut width) = (index, size);
+    while width > 1 {
+        if i ^ 1 < width {
+            let sibling = match siblings.next() {
+                Some(hash) => hash,
+                None => return false,
+            };
+            node = if i % 2 == 0 {
+                Hashed::from_preimage::<H>(&(&node, sibling)).hash
+            } else {
+                Hashed::from_preimage::<H>(&(sibling, &node)).hash
+            };
+        }
+        i /= 2;
+        width = (width + 1) / 2;
+    }
+    siblings.next().is_none() && sized_root::<H, O>(size, &node) == root.hash
+}
+
+/// `H(size || root)`. Hashes of leaves and of pairs of nodes have inputs of a different length,
+/// so a sized root is never mistaken for a node of a tree.
+fn sized_root<H, O>(size: u32, root: &O) -> O
+where
+    H: Digest,
+    O: Hash + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    Hashed::<(u64, &O), O>::from_preimage::<H>(&(u64::from(size), root)).hash
+}
+
+/// A merkle tree over a list of leaves, for generating proofs.
+///
+/// Leaves are paired left to right. When a level has an odd number of nodes, the last one is
+/// moved up to the next level as is.
+pub struct MerkleTree<T, O> {
+    /// The nodes of each level, starting with the hashed leaves.
+    layers: Vec<Vec<O>>,
+    _spook: PhantomData<T>,
+}
+
+impl<T, O: Clone> MerkleTree<T, O> {
+    pub fn new<H>(leafhashes: &[Hashed<T, O>]) -> Self
+    where
+        H: Digest,
+        O: Hash + FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        let mut layers: Vec<Vec<O>> = Vec::new();
+        layers.push(
+            leafhashes
+                .iter()
+                .map(|leaf| Hashed::<Hashed<T, O>, O>::from_preimage::<H>(leaf).hash)
+                .collect(),
+        );
+        while layers[layers.len() - 1].len() > 1 {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => Hashed::from_preimage::<H>(&(left, right)).hash,
+                    _ => pair[0].clone(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        let _spook = PhantomData;
+        Self { layers, _spook }
+    }
+
+    /// The root of the tree. The root of a tree without leaves is the default root, which no
+    /// proof is valid for.
+    pub fn root(&self) -> MerkleRoot<T, O>
+    where
+        O: Default,
+    {
+        let top = &self.layers[self.layers.len() - 1];
+        MerkleRoot::from_root(top.first().cloned().unwrap_or_default())
+    }
+
+    /// The root of the tree combined with its number of leaves, for use with
+    /// `verify_indexed_proof`. Proofs from `proof` and `multiproof` are not valid for it.
+    pub fn sized_root<H>(&self) -> MerkleRoot<T, O>
+    where
+        H: Digest,
+        O: Default + Hash + FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        let size = self.layers[0].len() as u32;
+        MerkleRoot::from_root(sized_root::<H, O>(size, &self.root().hash))
+    }
+
+    /// A proof that the leaf at `index` is at that position, see `verify_indexed_proof`. None if
+    /// the tree has no leaf at `index`.
+    pub fn indexed_proof(&self, index: u32) -> Option<IndexedProof<O>> {
+        let siblings = self
+            .proof(index)?
+            .into_iter()
+            .map(|pe| match pe {
+                ProofElement::Left(hash) | ProofElement::Right(hash) => hash,
+            })
+            .collect();
+        Some(IndexedProof { siblings })
+    }
+
+    /// A proof of inclusion for the leaf at `index`, see `verify_proof`. Indices are u32 like
+    /// the ones in `MultiProof` and `verify_indexed_proof`. None if the tree has no leaf at
+    /// `index`.
+    pub fn proof(&self, index: u32) -> Option<Vec<ProofElement<O>>> {
+        let mut i = index as usize;
+        if i >= self.layers[0].len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if i % 2 == 1 {
+                proof.push(ProofElement::Left(layer[i - 1].clone()));
+            } else if let Some(sibling) = layer.get(i + 1) {
+                proof.push(ProofElement::Right(sibling.clone()));
+            }
+            i /= 2;
+        }
+        Some(proof)
+    }
+
+    /// A proof of inclusion for the leaves at `indices`, see `verify_multiproof`. `indices`
+    /// may be given in any order, duplicates are ignored.
+    pub fn multiproof(&self, indices: &[u32]) -> MultiProof<O> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut hashes = Vec::new();
+        let mut known = indices.clone();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            for (n, i) in known.iter().enumerate() {
+                let sibling = (i ^ 1) as usize;
+                let sibling_known = if i % 2 == 0 {
+                    known.get(n + 1) == Some(&(i ^ 1))
+                } else {
+                    n > 0 && known[n - 1] == i ^ 1
+                };
+                if sibling < layer.len() && !sibling_known {
+                    hashes.push(layer[sibling].clone());
+                }
+            }
+            known = known.iter().map(|i| i / 2).collect();
+            known.dedup();
+        }
+        MultiProof {
+            leaf_count: self.layers[0].len() as u32,
+            indices,
+            hashes,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -87,6 +459,7 @@ mod test {
         static_assert_impls(Hashed::<Blah, [u8; 32]>::prehashed([0u8; 32]));
         static_assert_impls(MerkleRoot::<Blah, [u8; 32]>::from_root([0u8; 32]));
         static_assert_impls(ProofElement::<[u8; 32]>::Left([0u8; 32]));
+        static_assert_impls(IndexedProof::<[u8; 32]> { siblings: vec![] });
     }
 
     enum MTree<HashOut> {
@@ -203,6 +576,118 @@ mod test {
         assert!(verify_proof::<Blake2s, _, _>(&root, &proof, &leaf));
     }
 
+    #[test]
+    fn tree_proofs() {
+        for size in 1..10u8 {
+            let leaves: Vec<Hashed<(), [u8; 32]>> =
+                (0..size).map(|i| Hashed::prehashed(blash(i))).collect();
+            let tree = MerkleTree::new::<Blake2s>(&leaves);
+            let root = tree.root();
+            for (i, leaf) in (0..).zip(&leaves) {
+                let proof = tree.proof(i).unwrap();
+                assert!(verify_proof::<Blake2s, _, _>(&root, &proof, leaf));
+            }
+            // there is no leaf past the last one
+            assert_eq!(tree.proof(size as u32), None);
+            assert_eq!(tree.indexed_proof(size as u32), None);
+            assert_eq!(tree.proof(u32::max_value()), None);
+        }
+        let tree = MerkleTree::<(), [u8; 32]>::new::<Blake2s>(&[]);
+        assert_eq!(tree.root(), Default::default());
+        assert_eq!(tree.proof(0), None);
+    }
+
+    #[test]
+    fn compact_proof() {
+        for depth in 0..10 {
+            let tree = MTree::<[u8; 32]>::generate(depth);
+            let (proof, leaf) = tree.random_proof::<Blake2s>();
+            let leaf = Hashed::<(), _>::prehashed(*leaf);
+            let root = MerkleRoot::from_root(tree.root_hash::<Blake2s>());
+            let compact = CompactProof::from(proof.clone());
+            assert_eq!(compact.sides.len(), (proof.len() + 7) / 8);
+            assert!(verify_compact_proof::<Blake2s, _, _>(
+                &root, &compact, &leaf
+            ));
+            assert_eq!(Vec::try_from(compact.clone()), Ok(proof.clone()));
+            // a tag per level costs more than the bitfield and its length from 3 levels up
+            if proof.len() > 2 {
+                assert!(compact.encode().len() < proof.encode().len());
+            }
+        }
+
+        let proof = vec![
+            ProofElement::Left([1u8; 32]),
+            ProofElement::Right([2u8; 32]),
+            ProofElement::Left([3u8; 32]),
+        ];
+        let compact = CompactProof::from(proof);
+        assert_eq!(compact.sides, vec![0b101]);
+        let mut padded = compact.clone();
+        padded.sides[0] |= 0b1000;
+        assert!(Vec::try_from(padded).is_err());
+        let mut long = compact.clone();
+        long.sides.push(0);
+        assert!(Vec::try_from(long).is_err());
+        let mut short = compact;
+        short.sides.clear();
+        assert!(Vec::try_from(short).is_err());
+    }
+
+    #[test]
+    fn multiproof() {
+        let leaves: Vec<Hashed<(), [u8; 32]>> =
+            (0..7u8).map(|i| Hashed::prehashed(blash(i))).collect();
+        let tree = MerkleTree::new::<Blake2s>(&leaves);
+        let root = tree.root();
+        let verify = |proof: &MultiProof<[u8; 32]>, leaves: &[Hashed<(), [u8; 32]>]| {
+            verify_multiproof::<Blake2s, _, _>(&root, proof, leaves)
+        };
+
+        // every subset of leaves
+        for subset in 1..(1u32 << leaves.len()) {
+            let indices: Vec<u32> = (0..leaves.len() as u32)
+                .filter(|i| subset & (1 << i) != 0)
+                .collect();
+            let proven: Vec<_> = indices
+                .iter()
+                .map(|i| leaves[*i as usize].clone())
+                .collect();
+            let proof = tree.multiproof(&indices);
+            assert!(verify(&proof, &proven));
+            // shared siblings are only sent once
+            let separate: usize = indices.iter().map(|i| tree.proof(*i).unwrap().len()).sum();
+            assert!(proof.hashes.len() <= separate);
+        }
+
+        let proof = tree.multiproof(&[5, 1, 1]);
+        assert_eq!(proof.indices, vec![1, 5]);
+        let proven = [leaves[1].clone(), leaves[5].clone()];
+        assert!(verify(&proof, &proven));
+        // wrong leaves, wrong order, missing or extra hashes, out of range indices
+        assert!(!verify(&proof, &[leaves[1].clone(), leaves[4].clone()]));
+        assert!(!verify(&proof, &[leaves[5].clone(), leaves[1].clone()]));
+        assert!(!verify(&proof, &proven[..1]));
+        let mut short = proof.clone();
+        short.hashes.pop();
+        assert!(!verify(&short, &proven));
+        let mut long = proof.clone();
+        long.hashes.push([0u8; 32]);
+        assert!(!verify(&long, &proven));
+        let mut resized = proof.clone();
+        resized.leaf_count = 5;
+        assert!(!verify(&resized, &proven));
+        let mut unsorted = proof.clone();
+        unsorted.indices.reverse();
+        assert!(!verify(&unsorted, &[leaves[5].clone(), leaves[1].clone()]));
+        let empty = MultiProof {
+            leaf_count: 7,
+            indices: vec![],
+            hashes: vec![],
+        };
+        assert!(!verify(&empty, &[]));
+    }
+
     #[test]
     fn invalid_proof() {
         use sha2::Sha256;
@@ -271,4 +756,60 @@ mod test {
         );
         assert!(sha(&b"hello hello"[..]) == sha((&b"hello"[..], &b" hello"[..])));
     }
+
+    #[test]
+    fn indexed_proofs() {
+        let leaves: Vec<Hashed<(), [u8; 32]>> =
+            (0..10u8).map(|i| Hashed::prehashed(blash(i))).collect();
+        for size in 1..=leaves.len() {
+            let tree = MerkleTree::new::<Blake2s>(&leaves[..size]);
+            let root = tree.sized_root::<Blake2s>();
+            let size = size as u32;
+            for (index, leaf) in (0..).zip(&leaves[..size as usize]) {
+                let proof = tree.indexed_proof(index).unwrap();
+                let verify = |index, size, proof: &IndexedProof<[u8; 32]>| {
+                    verify_indexed_proof::<Blake2s, _, _>(&root, index, size, proof, leaf)
+                };
+                assert!(verify(index, size, &proof));
+                assert!(!verify(index ^ 1, size, &proof));
+                assert!(!verify(index, size + 1, &proof));
+                assert!(!verify(index, size - 1, &proof));
+                let mut extended = proof.clone();
+                extended.siblings.push(blash(0u8));
+                assert!(!verify(index, size, &extended));
+                if let Some((_, truncated)) = proof.siblings.split_last() {
+                    let truncated = IndexedProof {
+                        siblings: truncated.to_vec(),
+                    };
+                    assert!(!verify(index, size, &truncated));
+                }
+                // the plain root and the sized root are not interchangeable
+                assert!(!verify_proof::<Blake2s, _, _>(
+                    &root,
+                    &tree.proof(index).unwrap(),
+                    leaf
+                ));
+            }
+        }
+
+        // the left half of a tree of 8 leaves is the tree of its first 4 leaves, but proofs in
+        // one do not carry over to the other
+        let small = MerkleTree::new::<Blake2s>(&leaves[..4]);
+        let large = MerkleTree::new::<Blake2s>(&leaves[..8]);
+        assert_eq!(small.root().hash, large.layers[2][0]);
+        let mut truncated = large.indexed_proof(1).unwrap();
+        truncated.siblings.pop();
+        assert_eq!(Some(truncated.clone()), small.indexed_proof(1));
+        let claims = [
+            (large.sized_root::<Blake2s>(), 4),
+            (small.sized_root::<Blake2s>(), 8),
+        ];
+        for (root, size) in claims.iter() {
+            for proof in [&truncated, &large.indexed_proof(1).unwrap()].iter() {
+                assert!(!verify_indexed_proof::<Blake2s, _, _>(
+                    root, 1, *size, proof, &leaves[1]
+                ));
+            }
+        }
+    }
 }
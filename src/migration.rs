@@ -0,0 +1,266 @@
+//! Migration of storage written by earlier versions of this pallet.
+//!
+//! `StorageVersion` records the storage layout in use. On runtime upgrade, `migrate` runs the
+//! steps between the recorded layout and `Releases::CURRENT`, one release at a time. Steps that
+//! touch every entry only do a bounded part of the work on upgrade and continue in
+//! `on_initialize`, see `park_v1_entries`.
+
+use crate::hasher::Hashed;
+use crate::merkle::MerkleRoot;
+use crate::{
+    Anchors, Document, LegacyAnchors, LegacySuspendedLeaves, Module, ParkingCursor, Releases,
+    Revokable, StorageVersion, SuspendedLeaves, Trait, UnixTimeSeconds,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+    storage::{unhashed, StoragePrefixedMap},
+    traits::Get,
+    weights::Weight,
+    IterableStorageMap, StorageDoubleMap, StorageMap, StorageValue,
+};
+
+/// Bring storage up to date with `Releases::CURRENT`. Returns the weight consumed.
+pub fn migrate<T: Trait>() -> Weight {
+    let mut version = StorageVersion::get();
+    let mut weight = T::DbWeight::get().reads(1);
+    while let Some((next, step)) = upgrade_from::<T>(version) {
+        version = next;
+        StorageVersion::put(version);
+        weight += step + T::DbWeight::get().writes(1);
+    }
+    weight
+}
+
+/// Migrate storage from `version` to the release that follows it. Returns that release and the
+/// weight consumed, or None if `version` is the current release.
+fn upgrade_from<T: Trait>(version: Releases) -> Option<(Releases, Weight)> {
+    match version {
+        Releases::V1_0_0 => {
+            ParkingCursor::put(deprecated::Anchors::<T>::final_prefix().to_vec());
+            let weight = T::DbWeight::get().writes(1) + park_v1_entries::<T>();
+            Some((Releases::V2_0_0, weight))
+        }
+        Releases::V2_0_0 => None,
+    }
+}
+
+/// The storage layout used before `Anchors` and `SuspendedLeaves` became double maps.
+///
+/// Entries of these maps are stored under the blake2_256 hash of their key, below the same prefix
+/// as the double maps that replaced them.
+pub mod deprecated {
+    use crate::hasher::Hashed;
+    use crate::merkle::MerkleRoot;
+    use crate::{Document, Revokable, Trait, UnixTimeSeconds};
+    use frame_support::{decl_module, decl_storage};
+
+    decl_module! {
+        pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+    }
+
+    decl_storage! {
+        pub trait Store for Module<T: Trait> as TemplateModule {
+            pub Anchors: map hasher(blake2_256) (
+                MerkleRoot<T::AccountId, T::TreeHashOut>,
+                MerkleRoot<Document, T::TreeHashOut>
+            ) => Option<Revokable<T::BlockNumber>>;
+
+            pub SuspendedLeaves: map hasher(blake2_256) (
+                MerkleRoot<T::AccountId, T::TreeHashOut>,
+                Hashed<Document, T::TreeHashOut>
+            ) => Option<UnixTimeSeconds>;
+        }
+    }
+}
+
+/// Move entries of the V1_0_0 layout to `LegacyAnchors` and `LegacySuspendedLeaves`, visiting
+/// at most `T::MigrationKeysPerBlock` storage keys after `ParkingCursor`. Runs on upgrade and then
+/// in `on_initialize` until every entry is moved, which removes the cursor. Returns the weight
+/// consumed.
+///
+/// The key of a V1_0_0 entry can not be recovered from its hash, so the entries can not be written
+/// to the double maps here. They are kept under their hash until `migrate_anchor` or
+/// `migrate_suspension` is called with their key. Moving them out of the prefix of the double
+/// maps keeps `Anchors` and `SuspendedLeaves` iterable. Entries not moved yet are looked up where
+/// they are.
+pub fn park_v1_entries<T: Trait>() -> Weight {
+    let mut cursor = match ParkingCursor::get() {
+        Some(cursor) => cursor,
+        None => return T::DbWeight::get().reads(1),
+    };
+    let anchors = deprecated::Anchors::<T>::final_prefix();
+    let suspensions = deprecated::SuspendedLeaves::<T>::final_prefix();
+    let (mut reads, mut moved) = (1, 0);
+    let mut done = false;
+    for _ in 0..T::MigrationKeysPerBlock::get().max(1) {
+        let in_anchors = cursor.starts_with(&anchors);
+        let prefix = if in_anchors { &anchors } else { &suspensions };
+        reads += 1;
+        match sp_io::storage::next_key(&cursor).filter(|k| k.starts_with(prefix)) {
+            Some(key) => {
+                let parked = if in_anchors {
+                    park(
+                        &key,
+                        prefix.len(),
+                        |hash, anchor: Revokable<T::BlockNumber>| {
+                            LegacyAnchors::<T>::insert(hash, anchor)
+                        },
+                    )
+                } else {
+                    park(&key, prefix.len(), |hash, end: UnixTimeSeconds| {
+                        LegacySuspendedLeaves::insert(hash, end)
+                    })
+                };
+                if parked {
+                    reads += 1;
+                    moved += 1;
+                }
+                cursor = key;
+            }
+            None if in_anchors => cursor = suspensions.to_vec(),
+            None => {
+                done = true;
+                break;
+            }
+        }
+    }
+    if done {
+        ParkingCursor::kill();
+    } else {
+        ParkingCursor::put(cursor);
+    }
+    // Each entry is found with one read and taken with another, then written to its new place.
+    // The cursor is read and written once.
+    T::DbWeight::get().reads_writes(reads, 2 * moved + 1)
+}
+
+/// Move the V1_0_0 entry at `key`, below a prefix of `prefix_len` bytes, to `insert`, keyed by
+/// the hash it was stored under. Returns whether there was such an entry.
+fn park<V: Decode>(key: &[u8], prefix_len: usize, insert: impl Fn([u8; 32], V)) -> bool {
+    let mut hash = [0u8; 32];
+    // Entries of the double maps have longer keys, they are left alone.
+    if key.len() != prefix_len + hash.len() {
+        return false;
+    }
+    hash.copy_from_slice(&key[prefix_len..]);
+    match unhashed::take(key) {
+        Some(value) => {
+            insert(hash, value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drop the V1_0_0 entries stored under the given hashes, whether they were moved by
+/// `park_v1_entries` yet or not.
+pub fn clear<T: Trait>(anchors: &[[u8; 32]], suspensions: &[[u8; 32]]) {
+    let anchors_prefix = deprecated::Anchors::<T>::final_prefix();
+    for hash in anchors {
+        LegacyAnchors::<T>::remove(hash);
+        unhashed::kill(&[&anchors_prefix[..], &hash[..]].concat());
+    }
+    let suspensions_prefix = deprecated::SuspendedLeaves::<T>::final_prefix();
+    for hash in suspensions {
+        LegacySuspendedLeaves::remove(hash);
+        unhashed::kill(&[&suspensions_prefix[..], &hash[..]].concat());
+    }
+}
+
+/// The hash the V1_0_0 entry of `key` was stored under.
+fn legacy_hash(key: impl Encode) -> [u8; 32] {
+    sp_io::hashing::blake2_256(&key.encode())
+}
+
+/// The anchor of `root` under `admins`, including an entry of the V1_0_0 layout that was not
+/// migrated yet.
+///
+/// The anchor may have been revoked or created again under the current layout before it was
+/// migrated. A revocation in either layout wins, otherwise the legacy entry, which is the older
+/// one, is used.
+pub fn anchor<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    root: &MerkleRoot<Document, T::TreeHashOut>,
+) -> Option<Revokable<T::BlockNumber>> {
+    let legacy = LegacyAnchors::<T>::get(legacy_hash((admins, root)))
+        .or_else(|| deprecated::Anchors::<T>::get((admins, root)));
+    merge_anchors(Anchors::<T>::get(admins, root), legacy)
+}
+
+fn merge_anchors<B>(
+    current: Option<Revokable<B>>,
+    legacy: Option<Revokable<B>>,
+) -> Option<Revokable<B>> {
+    match (current, legacy) {
+        (Some(Revokable::Revoked), _) => Some(Revokable::Revoked),
+        (current, None) => current,
+        (_, legacy) => legacy,
+    }
+}
+
+/// The end of the suspension of `leaf` by `admins`, including an entry of the V1_0_0 layout that
+/// was not migrated yet. If the leaf was suspended in both layouts, the later end is used.
+pub fn suspension<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    leaf: &Hashed<Document, T::TreeHashOut>,
+) -> Option<UnixTimeSeconds> {
+    let legacy = LegacySuspendedLeaves::get(legacy_hash((admins, leaf)))
+        .or_else(|| deprecated::SuspendedLeaves::<T>::get((admins, leaf)));
+    merge_suspensions(SuspendedLeaves::<T>::get(admins, leaf), legacy)
+}
+
+fn merge_suspensions(
+    current: Option<UnixTimeSeconds>,
+    legacy: Option<UnixTimeSeconds>,
+) -> Option<UnixTimeSeconds> {
+    match (current, legacy) {
+        (Some(current), Some(legacy)) => Some(current.max(legacy)),
+        (current, legacy) => current.or(legacy),
+    }
+}
+
+/// Whether any entry of the V1_0_0 layout was not migrated yet.
+pub fn unmigrated<T: Trait>() -> bool {
+    ParkingCursor::exists()
+        || LegacyAnchors::<T>::iter().next().is_some()
+        || LegacySuspendedLeaves::iter().next().is_some()
+}
+
+/// Move the anchor of `root` under `admins` from `LegacyAnchors` to `Anchors`, merged as in
+/// `anchor`. Returns whether there was an entry to move.
+pub fn migrate_anchor<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    root: &MerkleRoot<Document, T::TreeHashOut>,
+) -> bool {
+    let legacy = LegacyAnchors::<T>::take(legacy_hash((admins, root)))
+        .or_else(|| deprecated::Anchors::<T>::take((admins, root)));
+    let legacy = match legacy {
+        Some(legacy) => legacy,
+        None => return false,
+    };
+    let merged = match Anchors::<T>::get(admins, root) {
+        Some(Revokable::Revoked) => Revokable::Revoked,
+        _ => legacy,
+    };
+    Anchors::<T>::insert(admins, root, merged);
+    Module::<T>::index_anchor(admins, root);
+    true
+}
+
+/// Move the suspension of `leaf` by `admins` from `LegacySuspendedLeaves` to `SuspendedLeaves`,
+/// merged as in `suspension`. Returns whether there was an entry to move.
+pub fn migrate_suspension<T: Trait>(
+    admins: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    leaf: &Hashed<Document, T::TreeHashOut>,
+) -> bool {
+    let legacy = LegacySuspendedLeaves::take(legacy_hash((admins, leaf)))
+        .or_else(|| deprecated::SuspendedLeaves::<T>::take((admins, leaf)));
+    let legacy = match legacy {
+        Some(legacy) => legacy,
+        None => return false,
+    };
+    SuspendedLeaves::<T>::mutate(admins, leaf, |current| {
+        *current = merge_suspensions(*current, Some(legacy));
+    });
+    true
+}
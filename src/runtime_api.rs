@@ -0,0 +1,19 @@
+//! Runtime API for querying anchors from outside the runtime.
+
+use crate::merkle::MerkleRoot;
+use crate::Document;
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait PoeApi<AccountId, TreeHashOut>
+    where
+        AccountId: Codec,
+        TreeHashOut: Codec,
+    {
+        /// The admin sets `root` is anchored or revoked under.
+        fn anchors_for_root(
+            root: MerkleRoot<Document, TreeHashOut>,
+        ) -> Vec<MerkleRoot<AccountId, TreeHashOut>>;
+    }
+}
@@ -0,0 +1,371 @@
+//! Snapshots of all anchoring data, for moving a registry from one chain to another.
+//!
+//! A snapshot holds every entry of `Anchors` and `SuspendedLeaves`, along with the validity
+//! periods, rotations, supersessions, disabled admins, delegations, revocation registries and
+//! status lists that go with them. It is sealed with a checksum over its SCALE encoding so an
+//! entry lost or altered on the way is detected on import.
+//! Snapshots are imported at genesis or through the root-only `import_anchors` extrinsic.
+
+use crate::fromalt::FromAlt;
+use crate::hasher::Hashed;
+use crate::merkle::MerkleRoot;
+use crate::sparse::SparseMerkleRoot;
+use crate::{
+    migration, AdminRotations, AnchorValidity, Anchors, Delegation, Delegations, DisableVotes,
+    DisabledAdmins, Document, Error, Module, PreviousVersions, RevocationRoots, Revokable, Role,
+    RotatedFrom, StatusList, StatusLists, Supersessions, SuspendedLeaves, Trait, UnixTimeSeconds,
+    Validity,
+};
+use blake2::digest::{generic_array::GenericArray, Digest};
+use codec::{Decode, Encode};
+use frame_support::{traits::Get, StorageDoubleMap, StorageMap};
+#[cfg(feature = "std")]
+use frame_support::{IterableStorageDoubleMap, IterableStorageMap};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::vec::Vec;
+
+/// Format version of the snapshots written by this version of the pallet.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "std",
+    serde(bound(
+        serialize = "AccountId: Serialize, BlockNumber: Serialize, O: Serialize",
+        deserialize = "AccountId: Deserialize<'de>, BlockNumber: Deserialize<'de>, \
+                       O: Deserialize<'de>"
+    ))
+)]
+pub struct Snapshot<AccountId, BlockNumber, O> {
+    pub version: u32,
+    /// Every anchor as (admins, root, status).
+    pub anchors: Vec<(
+        MerkleRoot<AccountId, O>,
+        MerkleRoot<Document, O>,
+        Revokable<BlockNumber>,
+    )>,
+    /// Every suspension as (admins, leaf, suspension end).
+    pub suspensions: Vec<(
+        MerkleRoot<AccountId, O>,
+        Hashed<Document, O>,
+        UnixTimeSeconds,
+    )>,
+    /// Every validity period of an anchor as (admins, root, validity).
+    pub validity: Vec<(MerkleRoot<AccountId, O>, MerkleRoot<Document, O>, Validity)>,
+    /// Every rotation as (previous admins, root, new admins, block of the rotation).
+    pub rotations: Vec<(
+        MerkleRoot<AccountId, O>,
+        MerkleRoot<Document, O>,
+        MerkleRoot<AccountId, O>,
+        BlockNumber,
+    )>,
+    /// Every supersession as (admins, old root, new root).
+    pub supersessions: Vec<(
+        MerkleRoot<AccountId, O>,
+        MerkleRoot<Document, O>,
+        MerkleRoot<Document, O>,
+    )>,
+    /// Every disabled admin leaf as (admins, leaf).
+    pub disabled_admins: Vec<(MerkleRoot<AccountId, O>, Hashed<AccountId, O>)>,
+    /// Every pending vote to disable an admin leaf as (admins, leaf, voters).
+    pub disable_votes: Vec<(
+        MerkleRoot<AccountId, O>,
+        Hashed<AccountId, O>,
+        Vec<Hashed<AccountId, O>>,
+    )>,
+    /// Every delegation as (admins, delegate, role, delegation).
+    pub delegations: Vec<(
+        MerkleRoot<AccountId, O>,
+        AccountId,
+        Role,
+        Delegation<Hashed<AccountId, O>>,
+    )>,
+    /// The root of every revocation registry as (admins, root).
+    pub revocation_roots: Vec<(MerkleRoot<AccountId, O>, SparseMerkleRoot<Document, O>)>,
+    /// The hash of every status list as (admins, hash).
+    pub status_lists: Vec<(MerkleRoot<AccountId, O>, Hashed<StatusList, O>)>,
+    /// Hash of the SCALE encoding of `version` and all entries.
+    pub checksum: O,
+}
+
+pub type SnapshotOf<T> = Snapshot<
+    <T as system::Trait>::AccountId,
+    <T as system::Trait>::BlockNumber,
+    <T as Trait>::TreeHashOut,
+>;
+
+impl<AccountId: Encode, BlockNumber: Encode, O: Encode> Snapshot<AccountId, BlockNumber, O> {
+    /// Set the current format version and a checksum matching the entries.
+    pub fn seal<H>(mut self) -> Self
+    where
+        H: Digest,
+        O: FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        self.version = SNAPSHOT_VERSION;
+        self.checksum = self.compute_checksum::<H>();
+        self
+    }
+
+    /// Whether `checksum` matches the entries of this snapshot.
+    pub fn checksum_valid<H>(&self) -> bool
+    where
+        H: Digest,
+        O: Eq + FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        self.compute_checksum::<H>() == self.checksum
+    }
+
+    /// Total number of entries.
+    pub fn len(&self) -> usize {
+        self.anchors.len()
+            + self.suspensions.len()
+            + self.validity.len()
+            + self.rotations.len()
+            + self.supersessions.len()
+            + self.disabled_admins.len()
+            + self.disable_votes.len()
+            + self.delegations.len()
+            + self.revocation_roots.len()
+            + self.status_lists.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn compute_checksum<H>(&self) -> O
+    where
+        H: Digest,
+        O: FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        let encoded = (
+            self.version,
+            &self.anchors,
+            &self.suspensions,
+            &self.validity,
+            &self.rotations,
+            &self.supersessions,
+            &self.disabled_admins,
+            &self.disable_votes,
+            &self.delegations,
+            &self.revocation_roots,
+            &self.status_lists,
+        )
+            .encode();
+        Hashed::<&[u8], O>::from_preimage::<H>(&encoded.as_slice()).hash
+    }
+}
+
+#[cfg(feature = "std")]
+impl<AccountId, BlockNumber, O> Snapshot<AccountId, BlockNumber, O>
+where
+    AccountId: Serialize + for<'de> Deserialize<'de>,
+    BlockNumber: Serialize + for<'de> Deserialize<'de>,
+    O: Serialize + for<'de> Deserialize<'de>,
+{
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Snapshot all anchoring data in storage.
+///
+/// Entries of an earlier storage layout can not be listed with their keys, so there is no
+/// snapshot until all of them are migrated, see `migration`, or dropped with
+/// `clear_legacy_entries`.
+#[cfg(feature = "std")]
+pub fn export<T: Trait>() -> Result<SnapshotOf<T>, Error<T>> {
+    if migration::unmigrated::<T>() {
+        return Err(Error::<T>::UnmigratedEntries);
+    }
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        anchors: Anchors::<T>::iter().collect(),
+        suspensions: SuspendedLeaves::<T>::iter().collect(),
+        validity: AnchorValidity::<T>::iter()
+            .map(|((admins, root), validity)| (admins, root, validity))
+            .collect(),
+        rotations: AdminRotations::<T>::iter()
+            .map(|((old, root), (new, block))| (old, root, new, block))
+            .collect(),
+        supersessions: Supersessions::<T>::iter()
+            .map(|((admins, old), new)| (admins, old, new))
+            .collect(),
+        disabled_admins: DisabledAdmins::<T>::iter()
+            .filter(|(_, disabled)| *disabled)
+            .map(|(key, _)| key)
+            .collect(),
+        disable_votes: DisableVotes::<T>::iter()
+            .map(|((admins, leaf), votes)| (admins, leaf, votes))
+            .collect(),
+        delegations: Delegations::<T>::iter()
+            .map(|((admins, delegate, role), delegation)| (admins, delegate, role, delegation))
+            .collect(),
+        revocation_roots: RevocationRoots::<T>::iter().collect(),
+        status_lists: StatusLists::<T>::iter().collect(),
+        checksum: Default::default(),
+    };
+    Ok(snapshot.seal::<T::TreeHash>())
+}
+
+/// Write the entries of `snapshot` to storage after checking its version and checksum.
+///
+/// Anchors and suspensions already present are merged with the imported ones the way
+/// `migration` merges entries of two storage layouts: a revocation on either side wins and the
+/// later of two suspension ends is kept. Likewise a disabled admin leaf stays disabled and votes
+/// to disable a leaf are combined. Of the other entries, the ones already present are kept.
+///
+/// The whole snapshot is refused if one of its rotations would not lead to an anchor or would
+/// form a cycle, see `rotation_valid`.
+pub fn import<T: Trait>(snapshot: &SnapshotOf<T>) -> Result<(), Error<T>> {
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(Error::<T>::UnsupportedSnapshotVersion);
+    }
+    if !snapshot.checksum_valid::<T::TreeHash>() {
+        return Err(Error::<T>::SnapshotChecksumMismatch);
+    }
+    for (old, root, new, _) in &snapshot.rotations {
+        if !AdminRotations::<T>::contains_key((old, root))
+            && !rotation_valid::<T>(snapshot, old, root, new)
+        {
+            return Err(Error::<T>::InvalidRotation);
+        }
+    }
+    for (admins, root, status) in &snapshot.anchors {
+        migration::migrate_anchor::<T>(admins, root);
+        let merged = match Anchors::<T>::get(admins, root) {
+            Some(Revokable::Revoked) => Revokable::Revoked,
+            Some(current) if *status != Revokable::Revoked => current,
+            _ => status.clone(),
+        };
+        if !Anchors::<T>::contains_key(admins, root) {
+            Module::<T>::index_anchor(admins, root);
+        }
+        Anchors::<T>::insert(admins, root, merged);
+    }
+    for (admins, leaf, end) in &snapshot.suspensions {
+        migration::migrate_suspension::<T>(admins, leaf);
+        SuspendedLeaves::<T>::mutate(admins, leaf, |current| {
+            *current = Some(current.map_or(*end, |c| c.max(*end)));
+        });
+    }
+    for (admins, root, validity) in &snapshot.validity {
+        AnchorValidity::<T>::mutate((admins, root), |current| {
+            current.get_or_insert(*validity);
+        });
+    }
+    for (old, root, new, block) in &snapshot.rotations {
+        if !AdminRotations::<T>::contains_key((old, root)) {
+            AdminRotations::<T>::insert((old, root), (new, block));
+            RotatedFrom::<T>::insert((new, root), old);
+        }
+    }
+    for (admins, old, new) in &snapshot.supersessions {
+        let key = (admins.clone(), old.clone());
+        if !Supersessions::<T>::contains_key(&key) {
+            let new_admins = Module::<T>::current_admins(admins, new);
+            PreviousVersions::<T>::insert((&new_admins, new), &key);
+            Supersessions::<T>::insert(&key, new);
+        }
+    }
+    for key in &snapshot.disabled_admins {
+        DisableVotes::<T>::remove(key);
+        DisabledAdmins::<T>::insert(key, true);
+    }
+    for (admins, leaf, votes) in &snapshot.disable_votes {
+        let key = (admins.clone(), leaf.clone());
+        if DisabledAdmins::<T>::get(&key) {
+            continue;
+        }
+        let mut merged = DisableVotes::<T>::get(&key);
+        for voter in votes {
+            if !merged.contains(voter) {
+                merged.push(voter.clone());
+            }
+        }
+        if merged.len() >= T::DisableThreshold::get().max(1) as usize {
+            DisableVotes::<T>::remove(&key);
+            DisabledAdmins::<T>::insert(&key, true);
+        } else {
+            DisableVotes::<T>::insert(&key, merged);
+        }
+    }
+    for (admins, delegate, role, delegation) in &snapshot.delegations {
+        Delegations::<T>::mutate((admins, delegate, role), |current| {
+            current.get_or_insert_with(|| delegation.clone());
+        });
+    }
+    for (admins, root) in &snapshot.revocation_roots {
+        RevocationRoots::<T>::mutate(admins, |current| {
+            current.get_or_insert_with(|| root.clone());
+        });
+    }
+    for (admins, list) in &snapshot.status_lists {
+        StatusLists::<T>::mutate(admins, |current| {
+            current.get_or_insert_with(|| list.clone());
+        });
+    }
+    Ok(())
+}
+
+/// Whether importing the rotation of `root` from `old` to `new` along with `snapshot` keeps the
+/// invariants `rotate_admins` upholds: `root` is no longer anchored under `old`, and following
+/// the rotations from `new`, stored or imported, ends at an admin set `root` is anchored under
+/// without coming back to an admin set already passed. `root` must not be rotated to `new` from
+/// another admin set as well, so `RotatedFrom` keeps a single entry per target.
+fn rotation_valid<T: Trait>(
+    snapshot: &SnapshotOf<T>,
+    old: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+    root: &MerkleRoot<Document, T::TreeHashOut>,
+    new: &MerkleRoot<T::AccountId, T::TreeHashOut>,
+) -> bool {
+    let anchored = |admins: &MerkleRoot<T::AccountId, T::TreeHashOut>| {
+        migration::anchor::<T>(admins, root).is_some()
+            || snapshot
+                .anchors
+                .iter()
+                .any(|(a, r, _)| a == admins && r == root)
+    };
+    // stored rotations are kept over imported ones
+    let rotated = |admins: &MerkleRoot<T::AccountId, T::TreeHashOut>| {
+        AdminRotations::<T>::get((admins, root))
+            .map(|(next, _)| next)
+            .or_else(|| {
+                snapshot
+                    .rotations
+                    .iter()
+                    .find(|(o, r, _, _)| o == admins && r == root)
+                    .map(|(_, _, next, _)| next.clone())
+            })
+    };
+    if anchored(old) {
+        return false;
+    }
+    if RotatedFrom::<T>::get((new, root)).map_or(false, |from| &from != old)
+        || snapshot
+            .rotations
+            .iter()
+            .any(|(o, r, n, _)| r == root && n == new && o != old)
+    {
+        return false;
+    }
+    let mut passed = Vec::new();
+    passed.push(old.clone());
+    let mut current = new.clone();
+    while !passed.contains(&current) {
+        if anchored(&current) {
+            return true;
+        }
+        match rotated(&current) {
+            Some(next) => passed.push(core::mem::replace(&mut current, next)),
+            None => return false,
+        }
+    }
+    false
+}
@@ -0,0 +1,492 @@
+//! Typechecked sparse merkle tree operations.
+//!
+//! A sparse merkle tree has one leaf for each of the 2^256 possible keys. A key is the hash of
+//! some `T`, read as a path from the root: most significant bit first, 0 is left. The leaf of a
+//! key in the set is the hash of the key, the leaf of any other key is `O::default()`.
+//!
+//! Since almost all leaves are empty, so are almost all subtrees. The hash of an empty subtree
+//! only depends on its height and is not included in proofs, which makes it possible to prove
+//! both that a key is in the set and that it is not.
+
+use crate::fromalt::FromAlt;
+use crate::hasher::{Hash, Hashed};
+use blake2::digest::{generic_array::GenericArray, Digest};
+use codec::{Decode, Encode};
+use core::{fmt::Debug, marker::PhantomData};
+use derivative::Derivative;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+
+/// Number of bits in a key, the height of the tree. Keys of any other length are refused, the
+/// pallet checks in `integrity_test` that its `TreeHashOut` is `DEPTH / 8` bytes long.
+pub const DEPTH: usize = 256;
+
+/// The most hashes `update_root` computes, given a table of empty subtrees: the leaf and one
+/// hash per level, for the tree before and after the update.
+pub const UPDATE_HASHES: usize = 2 * (DEPTH + 1);
+
+#[derive(Encode, Decode, Derivative)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "std",
+    serde(
+        transparent,
+        bound(serialize = "O: Serialize", deserialize = "O: Deserialize<'de>")
+    )
+)]
+#[derivative(
+    Clone(bound = "O: Clone"),
+    PartialEq(bound = "O: PartialEq"),
+    Eq(bound = "O: Eq"),
+    Debug(bound = "O: Debug")
+)]
+pub struct SparseMerkleRoot<T, O> {
+    hash: O,
+    #[cfg_attr(feature = "std", serde(skip))]
+    _spook: PhantomData<T>,
+}
+
+impl<T, O> SparseMerkleRoot<T, O> {
+    pub fn from_root(hash: O) -> Self {
+        let _spook = PhantomData;
+        Self { hash, _spook }
+    }
+
+    /// The root of the tree in which every leaf is empty.
+    pub fn empty<H>() -> Self
+    where
+        H: Digest,
+        O: Hash + Clone + Default + FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        EmptySubtrees::new::<H>().root()
+    }
+}
+
+/// The hashes of the empty subtrees of every height, computed once and shared by the proofs
+/// checked against them. Build one per batch of proofs, not one per proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmptySubtrees<O> {
+    /// `hashes[h]` is the hash of an empty subtree of height `h`.
+    hashes: Vec<O>,
+}
+
+impl<O> EmptySubtrees<O> {
+    /// Hash the empty subtrees of every height up to `DEPTH`, `DEPTH` hashes in total.
+    pub fn new<H>() -> Self
+    where
+        H: Digest,
+        O: Hash + Default + FromAlt<GenericArray<u8, H::OutputSize>>,
+    {
+        let mut hashes = Vec::with_capacity(DEPTH + 1);
+        hashes.push(O::default());
+        for height in 0..DEPTH {
+            hashes.push(merge::<H, O>(&hashes[height], &hashes[height]));
+        }
+        Self { hashes }
+    }
+
+    /// The hash of an empty subtree of `height`, at most `DEPTH`.
+    pub fn get(&self, height: usize) -> &O {
+        &self.hashes[height]
+    }
+
+    /// The root of the tree in which every leaf is empty.
+    pub fn root<T>(&self) -> SparseMerkleRoot<T, O>
+    where
+        O: Clone,
+    {
+        SparseMerkleRoot::from_root(self.hashes[DEPTH].clone())
+    }
+}
+
+impl<T, O: Hash> Hash for SparseMerkleRoot<T, O> {
+    fn hash<D: Digest>(&self, hasher: &mut D) {
+        self.hash.hash(hasher)
+    }
+}
+
+/// The path from the leaf of a key to the root.
+///
+/// Bit `i` of `non_empty` (least significant bit of byte `i / 8` first) is set when the sibling
+/// at height `i` is not an empty subtree. `siblings` holds the hashes of those siblings, from the
+/// leaf up. Which side a sibling is on follows from the key.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SparseProof<O> {
+    pub non_empty: [u8; DEPTH / 8],
+    pub siblings: Vec<O>,
+}
+
+/// Check that `key` is in the set with root `root`.
+pub fn verify_membership<H, T, O>(
+    root: &SparseMerkleRoot<T, O>,
+    key: &Hashed<T, O>,
+    proof: &SparseProof<O>,
+    empty: &EmptySubtrees<O>,
+) -> bool
+where
+    H: Digest,
+    O: Hash + Eq + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    compute_root::<H, T, O>(key, true, proof, empty).as_ref() == Some(&root.hash)
+}
+
+/// Check that `key` is not in the set with root `root`.
+pub fn verify_non_membership<H, T, O>(
+    root: &SparseMerkleRoot<T, O>,
+    key: &Hashed<T, O>,
+    proof: &SparseProof<O>,
+    empty: &EmptySubtrees<O>,
+) -> bool
+where
+    H: Digest,
+    O: Hash + Eq + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    compute_root::<H, T, O>(key, false, proof, empty).as_ref() == Some(&root.hash)
+}
+
+/// Check that `key` is in the set with root `root` if `present`, or not in it otherwise, and
+/// return the root of the set after adding `key` if `insert`, or removing it otherwise.
+///
+/// The siblings of a key do not change when it is added or removed, so `proof` proves the
+/// position of `key` in both trees.
+pub fn update_root<H, T, O>(
+    root: &SparseMerkleRoot<T, O>,
+    key: &Hashed<T, O>,
+    proof: &SparseProof<O>,
+    present: bool,
+    insert: bool,
+    empty: &EmptySubtrees<O>,
+) -> Option<SparseMerkleRoot<T, O>>
+where
+    H: Digest,
+    O: Hash + Eq + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    if compute_root::<H, T, O>(key, present, proof, empty)? != root.hash {
+        return None;
+    }
+    compute_root::<H, T, O>(key, insert, proof, empty).map(SparseMerkleRoot::from_root)
+}
+
+/// The root of the tree in which the leaf of `key` is full if `present` and empty otherwise,
+/// and the siblings of that leaf are given by `proof`. `None` if the proof or the key is
+/// malformed.
+fn compute_root<H, T, O>(
+    key: &Hashed<T, O>,
+    present: bool,
+    proof: &SparseProof<O>,
+    empty: &EmptySubtrees<O>,
+) -> Option<O>
+where
+    H: Digest,
+    O: Hash + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    let path = key.hash.as_ref();
+    let set_bits: usize = proof
+        .non_empty
+        .iter()
+        .map(|b| b.count_ones() as usize)
+        .sum();
+    if path.len() != DEPTH / 8 || set_bits != proof.siblings.len() {
+        return None;
+    }
+    let mut node = leaf::<H, T, O>(key, present);
+    let mut siblings = proof.siblings.iter();
+    for height in 0..DEPTH {
+        let sibling_empty = proof.non_empty[height / 8] & (1 << (height % 8)) == 0;
+        let sibling = if sibling_empty {
+            empty.get(height)
+        } else {
+            siblings.next()?
+        };
+        node = if bit(path, DEPTH - 1 - height) {
+            merge::<H, O>(sibling, &node)
+        } else {
+            merge::<H, O>(&node, sibling)
+        };
+    }
+    Some(node)
+}
+
+/// A sparse merkle tree, for generating proofs. Keeps every key in the set.
+///
+/// `O` must order like its bytes, as byte arrays do.
+pub struct SparseMerkleTree<H, T, O> {
+    keys: BTreeSet<O>,
+    empty: EmptySubtrees<O>,
+    _spook: PhantomData<(H, T)>,
+}
+
+impl<H, T, O> SparseMerkleTree<H, T, O>
+where
+    H: Digest,
+    O: Hash + Ord + Clone + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    /// A tree with every leaf empty.
+    pub fn new() -> Self {
+        let empty = EmptySubtrees::new::<H>();
+        let keys = BTreeSet::new();
+        let _spook = PhantomData;
+        Self {
+            keys,
+            empty,
+            _spook,
+        }
+    }
+
+    /// Add `key` to the set. Returns whether it was absent.
+    pub fn insert(&mut self, key: &Hashed<T, O>) -> bool {
+        self.keys.insert(key.hash.clone())
+    }
+
+    /// Remove `key` from the set. Returns whether it was present.
+    pub fn remove(&mut self, key: &Hashed<T, O>) -> bool {
+        self.keys.remove(&key.hash)
+    }
+
+    pub fn contains(&self, key: &Hashed<T, O>) -> bool {
+        self.keys.contains(&key.hash)
+    }
+
+    /// The table of empty subtrees this tree was built with, for checking its proofs.
+    pub fn empty_subtrees(&self) -> &EmptySubtrees<O> {
+        &self.empty
+    }
+
+    pub fn root(&self) -> SparseMerkleRoot<T, O> {
+        let keys: Vec<&O> = self.keys.iter().collect();
+        SparseMerkleRoot::from_root(self.subtree(&keys, 0))
+    }
+
+    /// A proof of the position of `key`. It proves membership if `key` is in the set and
+    /// non-membership otherwise.
+    pub fn proof(&self, key: &Hashed<T, O>) -> SparseProof<O> {
+        let path = key.hash.as_ref();
+        let mut keys: Vec<&O> = self.keys.iter().collect();
+        let mut non_empty = [0u8; DEPTH / 8];
+        let mut siblings = Vec::new();
+        // walk down from the root, collecting siblings from the top
+        for depth in 0..DEPTH {
+            let split = split_point(&keys, depth);
+            let (left, right) = keys.split_at(split);
+            let (next, sibling) = if bit(path, depth) {
+                (right, left)
+            } else {
+                (left, right)
+            };
+            if !sibling.is_empty() {
+                let height = DEPTH - 1 - depth;
+                non_empty[height / 8] |= 1 << (height % 8);
+                siblings.push(self.subtree(sibling, depth + 1));
+            }
+            keys = next.to_vec();
+        }
+        siblings.reverse();
+        SparseProof {
+            non_empty,
+            siblings,
+        }
+    }
+
+    /// The hash of the subtree at `depth` holding exactly `keys`, which are sorted and share
+    /// the path to that subtree.
+    fn subtree(&self, keys: &[&O], depth: usize) -> O {
+        match keys {
+            [] => self.empty.get(DEPTH - depth).clone(),
+            [key] if depth == DEPTH => leaf::<H, T, O>(&Hashed::prehashed((*key).clone()), true),
+            _ => {
+                let split = split_point(keys, depth);
+                let (left, right) = keys.split_at(split);
+                merge::<H, O>(
+                    &self.subtree(left, depth + 1),
+                    &self.subtree(right, depth + 1),
+                )
+            }
+        }
+    }
+}
+
+impl<H, T, O> Default for SparseMerkleTree<H, T, O>
+where
+    H: Digest,
+    O: Hash + Ord + Clone + Default + AsRef<[u8]> + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The leaf of `key`, full if `present`.
+fn leaf<H, T, O>(key: &Hashed<T, O>, present: bool) -> O
+where
+    H: Digest,
+    O: Hash + Default + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    if present {
+        Hashed::<Hashed<T, O>, O>::from_preimage::<H>(key).hash
+    } else {
+        O::default()
+    }
+}
+
+fn merge<H, O>(left: &O, right: &O) -> O
+where
+    H: Digest,
+    O: Hash + FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    Hashed::from_preimage::<H>(&(left, right)).hash
+}
+
+/// The number of `keys` that go left at `depth`. `keys` are sorted and share their first `depth`
+/// bits, so those come first.
+fn split_point<O: AsRef<[u8]>>(keys: &[&O], depth: usize) -> usize {
+    keys.iter()
+        .position(|k| bit(k.as_ref(), depth))
+        .unwrap_or(keys.len())
+}
+
+/// Bit `i` of `path`, most significant bit first.
+fn bit(path: &[u8], i: usize) -> bool {
+    path[i / 8] & (0x80 >> (i % 8)) != 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use blake2::Blake2s;
+
+    type Tree = SparseMerkleTree<Blake2s, u8, [u8; 32]>;
+
+    fn empty() -> EmptySubtrees<[u8; 32]> {
+        EmptySubtrees::new::<Blake2s>()
+    }
+
+    fn key(i: u8) -> Hashed<u8, [u8; 32]> {
+        Hashed::from_preimage::<Blake2s>(&i)
+    }
+
+    #[test]
+    fn empty_tree() {
+        let empty = empty();
+        let tree = Tree::new();
+        assert_eq!(tree.root(), SparseMerkleRoot::empty::<Blake2s>());
+        assert_eq!(tree.empty_subtrees(), &empty);
+        assert_eq!(empty.root(), tree.root());
+        let proof = tree.proof(&key(0));
+        assert!(proof.siblings.is_empty());
+        assert!(verify_non_membership::<Blake2s, _, _>(
+            &tree.root(),
+            &key(0),
+            &proof,
+            &empty
+        ));
+        assert!(!verify_membership::<Blake2s, _, _>(
+            &tree.root(),
+            &key(0),
+            &proof,
+            &empty
+        ));
+    }
+
+    #[test]
+    fn membership_and_non_membership() {
+        let empty = empty();
+        let mut tree = Tree::new();
+        for i in 0..20 {
+            tree.insert(&key(i));
+        }
+        let root = tree.root();
+        for i in 0..40 {
+            let proof = tree.proof(&key(i));
+            let member = i < 20;
+            assert_eq!(
+                verify_membership::<Blake2s, _, _>(&root, &key(i), &proof, &empty),
+                member
+            );
+            assert_eq!(
+                verify_non_membership::<Blake2s, _, _>(&root, &key(i), &proof, &empty),
+                !member
+            );
+        }
+        // a proof is bound to its key
+        let proof = tree.proof(&key(0));
+        assert!(!verify_membership::<Blake2s, _, _>(
+            &root,
+            &key(1),
+            &proof,
+            &empty
+        ));
+        // and to its root
+        tree.remove(&key(0));
+        assert!(!verify_membership::<Blake2s, _, _>(
+            &tree.root(),
+            &key(0),
+            &proof,
+            &empty
+        ));
+        assert!(verify_non_membership::<Blake2s, _, _>(
+            &tree.root(),
+            &key(0),
+            &proof,
+            &empty
+        ));
+    }
+
+    #[test]
+    fn malformed_proofs() {
+        let empty = empty();
+        let mut tree = Tree::new();
+        tree.insert(&key(0));
+        tree.insert(&key(1));
+        let root = tree.root();
+        let proof = tree.proof(&key(0));
+        assert!(verify_membership::<Blake2s, _, _>(
+            &root,
+            &key(0),
+            &proof,
+            &empty
+        ));
+        let mut extra = proof.clone();
+        extra.siblings.push([0u8; 32]);
+        assert!(!verify_membership::<Blake2s, _, _>(
+            &root,
+            &key(0),
+            &extra,
+            &empty
+        ));
+        let mut moved = proof.clone();
+        let height = (0..DEPTH)
+            .find(|h| moved.non_empty[h / 8] & (1 << (h % 8)) != 0)
+            .unwrap();
+        moved.non_empty[height / 8] ^= 1 << (height % 8);
+        moved.non_empty[(height + 1) / 8] ^= 1 << ((height + 1) % 8);
+        assert!(!verify_membership::<Blake2s, _, _>(
+            &root,
+            &key(0),
+            &moved,
+            &empty
+        ));
+    }
+
+    #[test]
+    fn update_root_matches_tree() {
+        let empty = empty();
+        let mut tree = Tree::new();
+        let mut root = tree.root();
+        for i in 0..10 {
+            let proof = tree.proof(&key(i));
+            root =
+                update_root::<Blake2s, _, _>(&root, &key(i), &proof, false, true, &empty).unwrap();
+            tree.insert(&key(i));
+            assert_eq!(root, tree.root());
+        }
+        // claiming the wrong previous state is refused
+        let proof = tree.proof(&key(3));
+        assert!(
+            update_root::<Blake2s, _, _>(&root, &key(3), &proof, false, true, &empty).is_none()
+        );
+        root = update_root::<Blake2s, _, _>(&root, &key(3), &proof, true, false, &empty).unwrap();
+        tree.remove(&key(3));
+        assert_eq!(root, tree.root());
+    }
+}
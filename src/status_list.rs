@@ -0,0 +1,330 @@
+//! Bitstring status lists in the style of StatusList2021, enabled with the `std` feature.
+//!
+//! A status list holds one bit per credential issued by an admin set, set when the credential is
+//! revoked. Lists are published GZIP-compressed off-chain and only the hash of the compressed
+//! bytes is anchored, with `update_status_list`. A verifier fetches the list, checks its hash
+//! against `status_list` of the admin set, decompresses it and looks up the index of the
+//! credential.
+//!
+//! `LeafIndex` maps the `Hashed<Document, _>` leaves of anchored documents to list indices. The
+//! index of a leaf is its position in the merkle tree it was anchored in, after the leaves of the
+//! trees the admin set anchored before it. Issuers recompute the index from the trees they keep
+//! to hand out proofs anyway, so it is neither stored nor published.
+
+use crate::fromalt::FromAlt;
+use crate::hasher::Hashed;
+use crate::{Document, StatusList};
+use blake2::digest::{generic_array::GenericArray, Digest};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+/// Minimum number of entries in a list, 16KiB uncompressed. Lists are padded to this size so a
+/// list does not tell much about the credentials pointing into it.
+pub const MIN_LEN: usize = 131_072;
+
+/// Maximum number of entries in a list, 2MiB uncompressed. Decompression stops there, so a small
+/// compressed list can not expand to fill memory.
+pub const MAX_LEN: usize = 128 * MIN_LEN;
+
+/// An uncompressed status list. Entry 0 is the most significant bit of the first byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitstring {
+    bits: Vec<u8>,
+}
+
+impl Bitstring {
+    /// A list of at least `len` entries, none of them set. The length is rounded up to
+    /// `MIN_LEN` and to a whole number of bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than `MAX_LEN`.
+    pub fn new(len: usize) -> Self {
+        assert!(len <= MAX_LEN, "status list longer than MAX_LEN");
+        let len = len.max(MIN_LEN);
+        Bitstring {
+            bits: vec![0; len / 8 + usize::from(len % 8 != 0)],
+        }
+    }
+
+    /// Number of entries in the list.
+    pub fn len(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Whether entry `index` is set. Entries past the end of the list are not set.
+    pub fn get(&self, index: usize) -> bool {
+        index < self.len() && self.bits[index / 8] & mask(index) != 0
+    }
+
+    /// Set entry `index` if `revoked`, clear it otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `len()`.
+    pub fn set(&mut self, index: usize, revoked: bool) {
+        let byte = &mut self.bits[index / 8];
+        if revoked {
+            *byte |= mask(index);
+        } else {
+            *byte &= !mask(index);
+        }
+    }
+
+    /// Indices of all entries that are set, in ascending order.
+    pub fn revoked(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(move |i| self.get(*i))
+    }
+
+    /// The GZIP-compressed list, as published.
+    pub fn compress(&self) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&self.bits)
+            .and_then(|()| encoder.finish())
+            .expect("writing to a Vec does not fail")
+    }
+
+    /// Read a GZIP-compressed list. Lists of less than `MIN_LEN` or more than `MAX_LEN` entries
+    /// are refused, without decompressing more than `MAX_LEN` entries.
+    pub fn decompress(compressed: &[u8]) -> io::Result<Self> {
+        let mut bits = Vec::new();
+        let limit = (MAX_LEN / 8) as u64;
+        GzDecoder::new(compressed)
+            .take(limit + 1)
+            .read_to_end(&mut bits)?;
+        if bits.len() * 8 > MAX_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "status list longer than MAX_LEN",
+            ));
+        }
+        if bits.len() * 8 < MIN_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "status list shorter than MIN_LEN",
+            ));
+        }
+        Ok(Bitstring { bits })
+    }
+}
+
+fn mask(index: usize) -> u8 {
+    0x80 >> (index % 8)
+}
+
+/// The hash anchored for a GZIP-compressed list.
+///
+/// Compression is not deterministic across implementations so the hash is taken over the exact
+/// bytes that are published, not over the uncompressed list.
+pub fn hash_compressed<H, O>(compressed: &[u8]) -> Hashed<StatusList, O>
+where
+    H: Digest,
+    O: FromAlt<GenericArray<u8, H::OutputSize>>,
+{
+    Hashed::prehashed(Hashed::<&[u8], O>::from_preimage::<H>(&compressed).hash)
+}
+
+/// Status list indices of the document leaves of the trees anchored by an admin set.
+///
+/// Trees are added in the order they were anchored, with their leaves in tree order as given to
+/// `merkle::MerkleTree::new`. Each leaf gets the next index, so the leaves of a tree keep their
+/// positions relative to each other. A leaf added again gets another index as well, and is revoked
+/// if any of its indices is set.
+#[derive(Clone, Debug)]
+pub struct LeafIndex<O> {
+    leaves: Vec<Hashed<Document, O>>,
+    indices: HashMap<O, Vec<usize>>,
+}
+
+impl<O: Clone + Eq + std::hash::Hash> LeafIndex<O> {
+    pub fn new() -> Self {
+        LeafIndex {
+            leaves: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// The index of the leaves of the trees in `trees`, in the order they were anchored.
+    pub fn from_trees<'a>(trees: impl IntoIterator<Item = &'a [Hashed<Document, O>]>) -> Self
+    where
+        O: 'a,
+    {
+        let mut index = Self::new();
+        for leaves in trees {
+            index.push_tree(leaves);
+        }
+        index
+    }
+
+    /// Add the leaves of the tree anchored after the ones already added. Returns the index of
+    /// its first leaf.
+    pub fn push_tree(&mut self, leaves: &[Hashed<Document, O>]) -> usize {
+        let first = self.leaves.len();
+        for leaf in leaves {
+            let next = self.leaves.len();
+            self.indices
+                .entry(leaf.hash.clone())
+                .or_default()
+                .push(next);
+            self.leaves.push(leaf.clone());
+        }
+        first
+    }
+
+    /// Number of leaves added, the number of entries the status list needs.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// The first index of `leaf`, see `indices_of` for leaves added more than once.
+    pub fn index_of(&self, leaf: &Hashed<Document, O>) -> Option<usize> {
+        self.indices_of(leaf).first().copied()
+    }
+
+    /// Every index of `leaf`, in the order they were added. Revoking the leaf sets all of them.
+    pub fn indices_of(&self, leaf: &Hashed<Document, O>) -> &[usize] {
+        self.indices
+            .get(&leaf.hash)
+            .map_or(&[], |indices| &indices[..])
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&Hashed<Document, O>> {
+        self.leaves.get(index)
+    }
+
+    /// Whether any index of `leaf` is set in `list`, None if `leaf` has no index.
+    pub fn is_revoked(&self, list: &Bitstring, leaf: &Hashed<Document, O>) -> Option<bool> {
+        let indices = self.indices.get(&leaf.hash)?;
+        Some(indices.iter().any(|index| list.get(*index)))
+    }
+
+    /// The leaves revoked in `list`, each once, at its first index set. Set entries without a
+    /// leaf are skipped.
+    pub fn revoked_leaves<'a>(
+        &'a self,
+        list: &'a Bitstring,
+    ) -> impl Iterator<Item = &'a Hashed<Document, O>> + 'a {
+        list.revoked().filter_map(move |index| {
+            let leaf = self.leaf(index)?;
+            let first = self.indices_of(leaf).iter().find(|i| list.get(**i));
+            Some(leaf).filter(|_| first == Some(&index))
+        })
+    }
+}
+
+impl<O: Clone + Eq + std::hash::Hash> Default for LeafIndex<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blake2::Blake2s;
+
+    fn leaf(i: u8) -> Hashed<Document, [u8; 32]> {
+        Hashed::prehashed([i; 32])
+    }
+
+    #[test]
+    fn bitstring() {
+        let mut list = Bitstring::new(10);
+        assert_eq!(list.len(), MIN_LEN);
+        assert_eq!(Bitstring::new(MIN_LEN + 1).len(), MIN_LEN + 8);
+
+        list.set(0, true);
+        list.set(9, true);
+        list.set(MIN_LEN - 1, true);
+        assert!(list.get(0) && list.get(9) && !list.get(1));
+        assert!(!list.get(MIN_LEN));
+        assert_eq!(list.bits[..2], [0x80, 0x40]);
+        assert_eq!(list.revoked().collect::<Vec<_>>(), [0, 9, MIN_LEN - 1]);
+
+        list.set(9, false);
+        assert_eq!(list.revoked().collect::<Vec<_>>(), [0, MIN_LEN - 1]);
+    }
+
+    #[test]
+    fn compression() {
+        let mut list = Bitstring::new(0);
+        list.set(42, true);
+        let compressed = list.compress();
+        assert!(compressed.len() < list.len() / 8 / 16);
+        assert_eq!(Bitstring::decompress(&compressed).unwrap(), list);
+        assert!(Bitstring::decompress(&compressed[1..]).is_err());
+
+        // lists outside of MIN_LEN..=MAX_LEN are refused, however well they compress
+        let short = Bitstring {
+            bits: vec![0; MIN_LEN / 8 - 1],
+        };
+        assert!(Bitstring::decompress(&short.compress()).is_err());
+        let full = Bitstring::new(MAX_LEN);
+        assert_eq!(Bitstring::decompress(&full.compress()).unwrap(), full);
+        let bomb = Bitstring {
+            bits: vec![0; 2 * MAX_LEN / 8],
+        }
+        .compress();
+        assert!(bomb.len() < MIN_LEN / 8);
+        assert!(Bitstring::decompress(&bomb).is_err());
+
+        let hash = hash_compressed::<Blake2s, [u8; 32]>(&compressed);
+        assert_eq!(hash.hash, Blake2s::digest(&compressed).as_slice());
+        list.set(42, false);
+        assert_ne!(hash_compressed::<Blake2s, [u8; 32]>(&list.compress()), hash);
+    }
+
+    #[test]
+    fn leaf_index() {
+        let first = [leaf(7), leaf(3)];
+        let second = [leaf(4), leaf(7), leaf(8)];
+        let mut index = LeafIndex::new();
+        assert_eq!(index.push_tree(&first), 0);
+        assert_eq!(index.push_tree(&second), 2);
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.index_of(&leaf(3)), Some(1));
+        assert_eq!(index.index_of(&leaf(8)), Some(4));
+        // a leaf in two trees has an index in each
+        assert_eq!(index.index_of(&leaf(7)), Some(0));
+        assert_eq!(index.indices_of(&leaf(7)), [0, 3]);
+        assert_eq!(index.indices_of(&leaf(9)), []);
+        assert_eq!(index.leaf(1), Some(&leaf(3)));
+        // the same trees always give the same indices
+        let rebuilt = LeafIndex::from_trees(vec![&first[..], &second[..]]);
+        assert_eq!(rebuilt.indices, index.indices);
+        assert_eq!(rebuilt.leaves, index.leaves);
+
+        let mut list = Bitstring::new(0);
+        list.set(1, true);
+        list.set(5, true);
+        assert_eq!(index.is_revoked(&list, &leaf(3)), Some(true));
+        assert_eq!(index.is_revoked(&list, &leaf(7)), Some(false));
+        assert_eq!(index.is_revoked(&list, &leaf(9)), None);
+        assert_eq!(index.revoked_leaves(&list).collect::<Vec<_>>(), [&leaf(3)]);
+
+        // setting any index of a leaf in two trees revokes it, and it is listed once
+        for set in [vec![3], vec![0, 3]].iter() {
+            let mut list = Bitstring::new(0);
+            for i in set {
+                list.set(*i, true);
+            }
+            let revoked: Vec<_> = index.revoked_leaves(&list).collect();
+            assert_eq!(revoked, [&leaf(7)]);
+            // both agree on every leaf
+            for i in 0..index.len() {
+                let leaf = index.leaf(i).unwrap();
+                assert_eq!(index.is_revoked(&list, leaf), Some(revoked.contains(&leaf)));
+            }
+        }
+    }
+}